@@ -27,12 +27,13 @@ import ViewerGoban, { type GameSelection } from "@/goban/viewer-goban";
 import { Button } from "@/ui-primitives/button";
 import trashSvg from "@/assets/icons/trash.svg";
 import arrowLeftSvg from "@/assets/icons/arrow-left.svg";
+import { decodeSearchResponse } from "@/binary-transport";
 import {
   toWasmSearch,
   type Game,
   type NextMove,
   type PlayerFilter,
-  type SearchReturn,
+  type SortBy,
   type WasmSearchMessage,
 } from "@/wasm-search-types";
 import {
@@ -41,7 +42,6 @@ import {
   getSelectedGameFromUrl,
   type GameFromUrl,
 } from "@/urls";
-import { SortBy } from "../../rust/wasm-search/pkg/wasm_search";
 
 export type AppProps = {
   initialBoard: BoardPosition;
@@ -132,7 +132,7 @@ export default function App({
         selectedGame.rotation ?? 0,
         selectedGame.is_mirrored ?? false,
         selectedGame.is_inverted ?? false,
-        selectedGame.last_move_matched,
+        selectedGame.last_move_matched ?? -1,
         selectedMoveNumber,
       );
     } else {
@@ -150,18 +150,17 @@ export default function App({
       setGameNotFound(false);
       clearTimeout(timer.current);
       timer.current = setTimeout(() => {
-        const position = toWasmSearch(board);
-        const nextColor = brushColor === SabakiColor.Black ? 0 : 1;
-        const positionBuf = new TextEncoder().encode(JSON.stringify(position));
         wasmSearchPostMessage({
           type: "search",
           payload: {
-            positionBuf,
-            nextColor,
+            position: toWasmSearch(board),
+            next_color: brushColor === SabakiColor.Black ? "Black" : "White",
             page: 0,
-            pageSize,
-            playerFilters,
-            sortBy: sortResultsBy,
+            page_size: pageSize,
+            player_filters: playerFilters,
+            pattern_filters: [],
+            sort_by: sortResultsBy,
+            board_size: board.length,
           },
         });
       }, 500);
@@ -179,18 +178,17 @@ export default function App({
   const loadMore = useCallback(() => {
     if (!isSearching) {
       setIsSearching(true);
-      const position = toWasmSearch(board);
-      const nextColor = brushColor === SabakiColor.Black ? 0 : 1;
-      const positionBuf = new TextEncoder().encode(JSON.stringify(position));
       wasmSearchPostMessage({
         type: "search",
         payload: {
-          positionBuf,
-          nextColor,
+          position: toWasmSearch(board),
+          next_color: brushColor === SabakiColor.Black ? "Black" : "White",
           page: currentPage + 1,
-          pageSize,
-          playerFilters,
-          sortBy: sortResultsBy,
+          page_size: pageSize,
+          player_filters: playerFilters,
+          pattern_filters: [],
+          sort_by: sortResultsBy,
+          board_size: board.length,
         },
       });
     }
@@ -221,7 +219,6 @@ export default function App({
 
       if (type === "result") {
         setIsSearching(false);
-        const jsonText = new TextDecoder().decode(payload);
         const {
           num_results,
           results,
@@ -229,7 +226,7 @@ export default function App({
           total_pages,
           current_page,
           player_counts,
-        } = JSON.parse(jsonText) as SearchReturn;
+        } = decodeSearchResponse(payload);
         if (current_page === 0) {
           setGames(() => results);
         } else {
@@ -241,39 +238,31 @@ export default function App({
         setNextMoves(() => next_moves);
         setCurrentPage(current_page);
         setHasMore(current_page < total_pages - 1);
-        setPlayerCounts(() => player_counts);
+        setPlayerCounts(() => player_counts as Record<number, number>);
       }
       if (type === "searchResultByPath") {
         // Check if payload is empty (game not found)
-        if (payload.length === 0) {
+        if (!payload) {
           console.warn(t("error.gameNotFound"));
           setGameNotFound(true);
           setLoadingGameSelection(false);
           return;
         }
 
-        try {
-          const jsonText = new TextDecoder().decode(payload);
-          const game = JSON.parse(jsonText) as Game;
+        const game = payload as Game;
 
-          // Handle both initial game load and back/forward navigation
-          const gameFromUrl = getSelectedGameFromUrl();
-          const moveNumber = gameFromUrl?.moveNumber ?? game.last_move_matched;
+        // Handle both initial game load and back/forward navigation
+        const gameFromUrl = getSelectedGameFromUrl();
+        const moveNumber =
+          gameFromUrl?.moveNumber ?? game.last_move_matched ?? -1;
 
-          setSelectedGame(() => game);
-          setSelectedMoveNumber(moveNumber);
-          setMoveNumbers((draft) => {
-            draft[game.path] = moveNumber;
-          });
-          setLoadingGameSelection(false);
-          setGameNotFound(false);
-        } catch (error) {
-          console.error("Failed to parse game data:", error);
-          setGameNotFound(true);
-          setLoadingGameSelection(false);
-          setSelectedGame(() => null);
-          setSelectedMoveNumber(0);
-        }
+        setSelectedGame(() => game);
+        setSelectedMoveNumber(moveNumber);
+        setMoveNumbers((draft) => {
+          draft[game.path] = moveNumber;
+        });
+        setLoadingGameSelection(false);
+        setGameNotFound(false);
       }
     };
 
@@ -294,7 +283,7 @@ export default function App({
   useEffect(() => {
     if (selectedGame) {
       setSelectedMoveNumber(
-        moveNumbers[selectedGame.path] ?? selectedGame.last_move_matched,
+        moveNumbers[selectedGame.path] ?? selectedGame.last_move_matched ?? -1,
       );
     } else {
       setSelectedMoveNumber(0);
@@ -0,0 +1,167 @@
+import {
+  PASS,
+  type Placement,
+  type SearchResponse,
+} from "@/wasm-search-types";
+
+// the stride of packed points on boards of any size
+const BOARD_SIZE = 19;
+
+// The subset of MessagePack written by rmp-serde for the search response
+class MessagePackReader {
+  private bytes: Uint8Array;
+  private view: DataView;
+  private offset = 0;
+
+  constructor(bytes: Uint8Array) {
+    this.bytes = bytes;
+    this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  }
+
+  read(): unknown {
+    const type = this.view.getUint8(this.offset++);
+    if (type <= 0x7f) return type;
+    if (type >= 0xe0) return type - 0x100;
+    if ((type & 0xf0) === 0x80) return this.readMap(type & 0x0f);
+    if ((type & 0xf0) === 0x90) return this.readArray(type & 0x0f);
+    if ((type & 0xe0) === 0xa0) return this.readString(type & 0x1f);
+    switch (type) {
+      case 0xc0:
+        return null;
+      case 0xc2:
+        return false;
+      case 0xc3:
+        return true;
+      case 0xc4:
+        return this.readBytes(this.readUint(1));
+      case 0xc5:
+        return this.readBytes(this.readUint(2));
+      case 0xc6:
+        return this.readBytes(this.readUint(4));
+      case 0xca:
+        return this.advance(4, this.view.getFloat32(this.offset));
+      case 0xcb:
+        return this.advance(8, this.view.getFloat64(this.offset));
+      case 0xcc:
+        return this.readUint(1);
+      case 0xcd:
+        return this.readUint(2);
+      case 0xce:
+        return this.readUint(4);
+      case 0xcf:
+        return this.advance(8, Number(this.view.getBigUint64(this.offset)));
+      case 0xd0:
+        return this.advance(1, this.view.getInt8(this.offset));
+      case 0xd1:
+        return this.advance(2, this.view.getInt16(this.offset));
+      case 0xd2:
+        return this.advance(4, this.view.getInt32(this.offset));
+      case 0xd3:
+        return this.advance(8, Number(this.view.getBigInt64(this.offset)));
+      case 0xd9:
+        return this.readString(this.readUint(1));
+      case 0xda:
+        return this.readString(this.readUint(2));
+      case 0xdb:
+        return this.readString(this.readUint(4));
+      case 0xdc:
+        return this.readArray(this.readUint(2));
+      case 0xdd:
+        return this.readArray(this.readUint(4));
+      case 0xde:
+        return this.readMap(this.readUint(2));
+      case 0xdf:
+        return this.readMap(this.readUint(4));
+      default:
+        throw new Error(`Unsupported MessagePack type 0x${type.toString(16)}`);
+    }
+  }
+
+  private advance<T>(length: number, value: T): T {
+    this.offset += length;
+    return value;
+  }
+
+  private readUint(length: 1 | 2 | 4): number {
+    const value =
+      length === 1
+        ? this.view.getUint8(this.offset)
+        : length === 2
+          ? this.view.getUint16(this.offset)
+          : this.view.getUint32(this.offset);
+    return this.advance(length, value);
+  }
+
+  private readBytes(length: number): Uint8Array {
+    return this.advance(
+      length,
+      this.bytes.subarray(this.offset, this.offset + length),
+    );
+  }
+
+  private readString(length: number): string {
+    return new TextDecoder().decode(this.readBytes(length));
+  }
+
+  private readArray(length: number): unknown[] {
+    return Array.from({ length }, () => this.read());
+  }
+
+  private readMap(length: number): Record<string, unknown> {
+    const map: Record<string, unknown> = {};
+    for (let i = 0; i < length; i++) {
+      const key = this.read();
+      map[String(key)] = this.read();
+    }
+    return map;
+  }
+}
+
+/**
+ * Unpacks placements in the encoding of `pack_placements`: a big-endian u16 count, 9 bits
+ * per point (`x * 19 + y`, or `19 * 19` for a pass) and then 1 bit per color (1 is black),
+ * most significant bit first
+ */
+export function unpackPlacements(packed: Uint8Array): Placement[] {
+  const length = (packed[0] << 8) | packed[1];
+  const bit = (start: number, index: number) =>
+    (packed[start + (index >> 3)] >> (7 - (index & 7))) & 1;
+  const colorsStart = 2 + Math.ceil((length * 9) / 8);
+
+  const placements: Placement[] = [];
+  for (let i = 0; i < length; i++) {
+    let value = 0;
+    for (let j = 0; j < 9; j++) {
+      value = (value << 1) | bit(2, i * 9 + j);
+    }
+    placements.push({
+      color: bit(colorsStart, i) ? "Black" : "White",
+      point:
+        value === BOARD_SIZE * BOARD_SIZE
+          ? PASS
+          : { x: Math.floor(value / BOARD_SIZE), y: value % BOARD_SIZE },
+    });
+  }
+  return placements;
+}
+
+/**
+ * Decodes a response of `WasmSearch.search_binary`, see `rust/search/src/binary.rs`
+ */
+export function decodeSearchResponse(bytes: Uint8Array): SearchResponse {
+  const { response, setup, setup_transformed, moves, moves_transformed } =
+    new MessagePackReader(bytes).read() as {
+      response: SearchResponse;
+      setup: Uint8Array[];
+      setup_transformed: Uint8Array[];
+      moves: Uint8Array[];
+      moves_transformed: Uint8Array[];
+    };
+  response.results.forEach((result, i) => {
+    result.setup = unpackPlacements(setup[i]);
+    result.setup_transformed = unpackPlacements(setup_transformed[i]);
+    result.moves = unpackPlacements(moves[i]);
+    result.moves_transformed = unpackPlacements(moves_transformed[i]);
+  });
+  return response;
+}
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Placement } from "./Placement";
+
+/**
+ * Where a stone of the query position was played in a matched game
+ *
+ * The stones are listed in the order of the query position. The transform that was applied
+ * to match them is the `rotation`, `is_mirrored` and `is_inverted` of the `SearchResult`.
+ */
+export type MatchedStone = { placement: Placement, move_number: number | null, is_replayed: boolean, };
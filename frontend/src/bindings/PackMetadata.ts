@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Information about a games database stored in its pack
+ */
+export type PackMetadata = { version: number, created_at: number, game_count: number, source: string, };
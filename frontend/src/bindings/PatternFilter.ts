@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Color } from "./Color";
+import type { Point } from "./Point";
+
+/**
+ * Filter for matching which player was on which side of the matched pattern
+ *
+ * Unlike `PlayerFilter`, which only checks that a player took part in the game, these are
+ * evaluated against the colors of the query stones, taking into account whether the colors
+ * were inverted to find the match.
+ *
+ * # Variants
+ * * `StonePlayedBy` - The query stone at `point` was played by the player with `player_id`
+ * * `ColorPlayedBy` - The query stones of `color` were played by the player with `player_id`
+ * * `ColorWon` - The player who played the query stones of `color` won the game
+ */
+export type PatternFilter = { "StonePlayedBy": { point: Point, player_id: number, } } | { "ColorPlayedBy": { color: Color, player_id: number, } } | { "ColorWon": { color: Color, } };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Color } from "./Color";
+import type { Point } from "./Point";
+
+export type Placement = { color: Color, point: Point, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Color } from "./Color";
+
+/**
+ * Filter for matching players by ID and optionally by color
+ *
+ * # Fields
+ * * `player_id` - The ID of the player to match
+ * * `color` - Optional color constraint:
+ *   - `None` - Match player regardless of color (black or white)
+ *   - `Some(Color::Black)` - Only match when player is playing black
+ *   - `Some(Color::White)` - Only match when player is playing white
+ */
+export type PlayerFilter = { player_id: number, color: Color | null, };
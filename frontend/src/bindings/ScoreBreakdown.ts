@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The parts that make up a `SearchResult` score
+ *
+ * The total score is `(transform + empty_surrounding) * empty_multiplier - move_penalty`.
+ */
+export type ScoreBreakdown = { transform: number, empty_surrounding: number, empty_multiplier: number, move_penalty: number, };
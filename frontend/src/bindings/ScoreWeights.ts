@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Weights used to rank search results, the defaults give the standard ranking
+ *
+ * # Fields
+ * * `original` - Score for matching the position as is, or mirrored within one quadrant
+ * * `inverted` - Score for matching with the colors inverted
+ * * `mirrored` - Score for matching the mirrored position when it spans quadrants
+ * * `mirrored_inverted` - Score for matching mirrored with colors inverted when it spans quadrants
+ * * `rotation_penalty` - Subtracted from the transform score when the match is rotated
+ * * `empty_surrounding` - Score per ring distance for each query stone with correctly empty
+ *   surroundings
+ * * `all_empty_multiplier` - Multiplier per ring where all surrounding points are correctly empty
+ * * `move_penalty` - Subtracted per move it took for the game to reach the position
+ */
+export type ScoreWeights = { original: number, inverted: number, mirrored: number, mirrored_inverted: number, rotation_penalty: number, empty_surrounding: number, all_empty_multiplier: number, move_penalty: number, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Color } from "./Color";
+import type { PatternFilter } from "./PatternFilter";
+import type { Placement } from "./Placement";
+import type { PlayerFilter } from "./PlayerFilter";
+import type { SortBy } from "./SortBy";
+
+/**
+ * A search for games containing a position
+ *
+ * # Fields
+ * * `position` - The query stones
+ * * `next_color` - Color of the next move, used for the suggested next moves
+ * * `page` - Page number for pagination (0-based)
+ * * `page_size` - Number of results per page
+ * * `player_filters` - All of these players must have played in a matching game
+ * * `pattern_filters` - All of these must hold for which player played which side of the pattern
+ * * `sort_by` - Order of the results
+ * * `board_size` - Only games on boards of this size are searched
+ *
+ * When deserializing only `position` is required, the rest default to the values of `new`.
+ */
+export type SearchRequest = { position: Array<Placement>, next_color: Color, page: number, page_size: number, player_filters: Array<PlayerFilter>, pattern_filters: Array<PatternFilter>, sort_by: SortBy, board_size: number, };
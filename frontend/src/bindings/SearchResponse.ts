@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NextMove } from "./NextMove";
+import type { SearchResult } from "./SearchResult";
+
+export type SearchResponse = { num_results: number, next_moves: Array<NextMove>, results: Array<SearchResult>, total_pages: number, current_page: number, player_counts: { [key in number]?: number }, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Color } from "./Color";
+import type { GameResult } from "./GameResult";
+import type { MatchedStone } from "./MatchedStone";
+import type { Placement } from "./Placement";
+import type { Player } from "./Player";
+import type { Rank } from "./Rank";
+import type { Rules } from "./Rules";
+import type { ScoreBreakdown } from "./ScoreBreakdown";
+import type { SgfDate } from "./SgfDate";
+
+export type SearchResult = { path: string, score: number, score_breakdown: ScoreBreakdown, last_move_matched: number | null, rotation: number, is_inverted: boolean, is_mirrored: boolean, all_empty_correctly_within: number, matched_stones: Array<MatchedStone>, setup: Array<Placement>, setup_transformed: Array<Placement>, moves: Array<Placement>, moves_transformed: Array<Placement>, variation: number | null, event: string, round: string, location: string, date: SgfDate | null, player_black: Player, player_white: Player, rank_black: Rank, rank_white: Rank, komi: number | null, rules: Rules | null, result: GameResult, board_size: number, handicap: number, first_color: Color, };
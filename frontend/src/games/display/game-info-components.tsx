@@ -56,7 +56,7 @@ export function FormattedDate({
 
   if (!date) return null;
 
-  if (date.YearMonthDay) {
+  if ("YearMonthDay" in date) {
     const [year, month, day] = date.YearMonthDay;
     return (
       <>
@@ -65,7 +65,7 @@ export function FormattedDate({
     );
   }
 
-  if (date.YearMonth) {
+  if ("YearMonth" in date) {
     const [year, month] = date.YearMonth;
     return (
       <>
@@ -76,7 +76,7 @@ export function FormattedDate({
     );
   }
 
-  if (date.Year) {
+  if ("Year" in date) {
     return (
       <>
         <span>{date.Year}</span>
@@ -91,25 +91,21 @@ function formatResult(result: GameResult | null): string {
   if (!result) return "Unknown";
   if (result === "Draw") return "Draw";
   if (result === "Void") return "Void";
-  if (result.Unknown) return result.Unknown;
-  if (result.Player) {
-    const [color, score] = result.Player;
-    const colorStr = color === "Black" ? "B" : "W";
-    if (!score) return `${colorStr}+?`;
-    if (score === "Resignation") return `${colorStr}+Resign`;
-    if (score === "Timeout") return `${colorStr}+Timeout`;
-    if (score === "Forfeit") return `${colorStr}+Forfeit`;
-    if (score.Points) return `${colorStr}+${score.Points.toFixed(1)}`;
-    return `${colorStr}+?`;
-  }
-  return "Unknown";
+  if ("Unknown" in result) return result.Unknown || "Unknown";
+  const [color, score] = result.Player;
+  const colorStr = color === "Black" ? "B" : "W";
+  if (!score) return `${colorStr}+?`;
+  if (score === "Resignation") return `${colorStr}+Resign`;
+  if (score === "Timeout") return `${colorStr}+Timeout`;
+  if (score === "Forfeit") return `${colorStr}+Forfeit`;
+  return `${colorStr}+${score.Points.toFixed(1)}`;
 }
 
 function formatRank(rank: Rank | null): string {
   if (!rank) return "(?)";
-  if (rank.Kyu) return `${rank.Kyu}k`;
-  if (rank.Dan) return `${rank.Dan}d`;
-  if (rank.Pro) return `${rank.Pro}p`;
+  if ("Kyu" in rank) return `${rank.Kyu}k`;
+  if ("Dan" in rank) return `${rank.Dan}d`;
+  if ("Pro" in rank) return `${rank.Pro}p`;
   if (rank.Custom) return `(${rank.Custom})`;
   return "(?)";
 }
@@ -119,12 +115,11 @@ function formatRules(
   t: (key: TranslationKey) => string,
 ): string {
   if (!rules) return t("rules.unknown");
-  if (rules.Chinese) return t("rules.chinese");
-  if (rules.Japanese) return t("rules.japanese");
-  if (rules.Korean) return t("rules.korean");
-  if (rules.Ing) return t("rules.ing");
-  if (rules.Custom) return rules.Custom;
-  return t("rules.unknown");
+  if (rules === "Chinese") return t("rules.chinese");
+  if (rules === "Japanese") return t("rules.japanese");
+  if (rules === "Korean") return t("rules.korean");
+  if (rules === "Ing") return t("rules.ing");
+  return rules.Custom || t("rules.unknown");
 }
 
 export type PlayerDisplayProps = {
@@ -149,13 +144,17 @@ export const PlayerDisplay = memo(function PlayerDisplay({
   const { t, locale } = useTranslations();
   const alt = isBlack ? t("alt.black") : t("alt.white");
 
-  const { name: playerName, aliases: playerAliases } = player.Id
-    ? getPlayerNames(player.Id[0], locale)
-    : { name: player.Unknown || "Unknown", aliases: [] };
+  const playerId = "Id" in player ? player.Id : null;
+  const { name: playerName, aliases: playerAliases } = playerId
+    ? getPlayerNames(playerId[0], locale)
+    : {
+        name: ("Unknown" in player && player.Unknown) || "Unknown",
+        aliases: [],
+      };
   const playerRank = formatRank(rank);
 
   // Get the original name from the game record if different from player_names.json
-  const originalName = player.Id?.[1];
+  const originalName = playerId?.[1];
   const showOriginalName = originalName && originalName !== playerName;
 
   const [isCircleHovered, setIsCircleHovered] = useState(false);
@@ -171,19 +170,19 @@ export const PlayerDisplay = memo(function PlayerDisplay({
   }, []);
 
   const handlePlayerNameClick = useCallback(() => {
-    if (onPlayerClick && player.Id) {
-      onPlayerClick(player.Id[0], "Any");
+    if (onPlayerClick && playerId) {
+      onPlayerClick(playerId[0], "Any");
     }
-  }, [onPlayerClick, player.Id]);
+  }, [onPlayerClick, playerId]);
 
   const handleCircleClick = useCallback(
     (e: React.MouseEvent) => {
       e.stopPropagation();
-      if (onPlayerClick && player.Id) {
-        onPlayerClick(player.Id[0], color);
+      if (onPlayerClick && playerId) {
+        onPlayerClick(playerId[0], color);
       }
     },
-    [onPlayerClick, player.Id, color],
+    [onPlayerClick, playerId, color],
   );
 
   return (
@@ -204,7 +203,7 @@ export const PlayerDisplay = memo(function PlayerDisplay({
                 <div
                   className={cn(
                     "w-7 h-7 flex-shrink-0 rounded flex items-center justify-center",
-                    onPlayerClick && player.Id && "cursor-pointer",
+                    onPlayerClick && playerId && "cursor-pointer",
                   )}
                   onClick={handleCircleClick}
                   onMouseEnter={() => {
@@ -224,7 +223,7 @@ export const PlayerDisplay = memo(function PlayerDisplay({
                   className={cn(
                     "font-medium rounded border",
                     isNameHovered ? "border-gray-400" : "border-transparent",
-                    onPlayerClick && player.Id && "cursor-pointer",
+                    onPlayerClick && playerId && "cursor-pointer",
                   )}
                   onClick={handlePlayerNameClick}
                   onMouseEnter={() => {
@@ -292,6 +291,14 @@ export function GameIcons({ game, className }: GameIconsProps) {
           title={t("tooltip.colorsInverted")}
         />
       )}
+      {game.variation !== null && (
+        <span
+          className="text-xs text-gray-600 whitespace-nowrap"
+          title={t("tooltip.variation")}
+        >
+          {t("info.variation")}
+        </span>
+      )}
     </div>
   );
 }
@@ -466,7 +473,7 @@ export function MoveInfo({ game, onSelectAtMove, className }: MoveInfoProps) {
     (e: React.MouseEvent) => {
       e.stopPropagation();
       if (onSelectAtMove) {
-        onSelectAtMove(game, game.last_move_matched);
+        onSelectAtMove(game, game.last_move_matched ?? -1);
       }
     },
     [onSelectAtMove, game],
@@ -494,7 +501,7 @@ export function MoveInfo({ game, onSelectAtMove, className }: MoveInfoProps) {
         onClick={handleMatchedMoveClick}
         title={t("info.clickToView")}
       >
-        {t("info.move")} {game.last_move_matched + 1}
+        {t("info.move")} {(game.last_move_matched ?? -1) + 1}
       </span>
       <span className="text-gray-300 whitespace-nowrap">/</span>
       <span
@@ -524,7 +531,7 @@ export function MoveInfoCompact({
     (e: React.MouseEvent) => {
       e.stopPropagation();
       if (onSelectAtMove) {
-        onSelectAtMove(game, game.last_move_matched);
+        onSelectAtMove(game, game.last_move_matched ?? -1);
       }
     },
     [onSelectAtMove, game],
@@ -548,7 +555,7 @@ export function MoveInfoCompact({
         onClick={handleMatchedMoveClick}
         title={t("info.clickToView")}
       >
-        {t("info.move")} {game.last_move_matched + 1}
+        {t("info.move")} {(game.last_move_matched ?? -1) + 1}
       </span>
       <span
         className="text-gray-300 text-2xl"
@@ -573,11 +580,21 @@ export type SGFDownloadProps = {
 
 export function SGFDownload({ game, className }: SGFDownloadProps) {
   const { t } = useTranslations();
+  // a game of a collection is downloaded with the whole file
+  const path = game.path.replace(/#\d+$/, "");
+  // and a game of an archive with the whole archive, its path continues inside the archive
+  const archive = path.match(/^(.*?\.(?:zip|tar|tar\.gz|tgz))\//);
+  // games of GIB, NGF and UGF files keep the extension in their path
+  const file = archive
+    ? archive[1]
+    : /\.(gib|ngf|ugf)$/.test(path)
+      ? path
+      : `${path}.sgf`;
 
   return (
     <a
-      href={`/sgfs/${game.path}.sgf`}
-      download={`${game.path}.sgf`}
+      href={`/sgfs/${file}`}
+      download={file}
       className={cn(
         "flex items-center gap-1 cursor-pointer hover:bg-gray-100 px-2 py-1 rounded justify-end",
         className,
@@ -309,7 +309,9 @@ export default function GamesList({
                 onSelect={onSelectGame}
                 onSelectAtMove={onSelectGameAtMove}
                 showAllResults={showAllResults}
-                moveNumber={moveNumbers[game.path] ?? game.last_move_matched}
+                moveNumber={
+                  moveNumbers[game.path] ?? game.last_move_matched ?? -1
+                }
                 onPlayerClick={onPlayerClick}
                 tinyVertexSize={tinyVertexSize}
               />
@@ -4,6 +4,7 @@ import {
   type BoardPosition,
   type SabakiMove,
 } from "@/sabaki-types";
+import { isPass } from "@/wasm-search-types";
 
 // LRU Cache for memoization
 class LRUCache<K, V> {
@@ -45,38 +46,47 @@ class LRUCache<K, V> {
 // Cache instance for board position calculations
 const boardPositionCache = new LRUCache<string, BoardPosition>(1000);
 
+function movesKey(moves: Array<SabakiMove>): string {
+  return moves
+    .map((move) => `${move.color}:${move.point.x},${move.point.y}`)
+    .join("|");
+}
+
 /**
- * Creates a cache key for the given moves and move number
+ * Creates a cache key for the given setup stones, moves and move number
  */
-function createCacheKey(moves: Array<SabakiMove>, moveNumber: number): string {
+function createCacheKey(
+  setup: Array<SabakiMove>,
+  moves: Array<SabakiMove>,
+  moveNumber: number,
+): string {
   // Only include moves up to moveNumber for the cache key
   const relevantMoves = moves.slice(0, moveNumber + 1);
-  const movesKey = relevantMoves
-    .map((move) => `${move.color}:${move.point.x},${move.point.y}`)
-    .join("|");
-  return `${movesKey}#${moveNumber}`;
+  return `${movesKey(setup)}/${movesKey(relevantMoves)}#${moveNumber}`;
 }
 
 /**
  * Calculates the board position after playing a sequence of moves up to a given move number.
  * Results are memoized for improved performance on repeated calculations.
  *
+ * @param setup - Stones placed before the first move, like handicap stones
  * @param moves - Array of moves in Sabaki format
- * @param moveNumber - The move number to calculate up to (0-based). Use -1 for empty board.
+ * @param moveNumber - The move number to calculate up to (0-based). Use -1 for the setup only.
  * @returns The board position as a signMap
  */
 export function calculateBoardPosition(
+  setup: Array<SabakiMove>,
   moves: Array<SabakiMove>,
   moveNumber: number,
 ): BoardPosition {
-  // If moveNumber is -1, return empty board (no caching needed)
-  if (moveNumber < 0) {
+  // Without setup stones before move 1, return empty board (no caching needed)
+  if (moveNumber < 0 && setup.length === 0) {
     const board = new GoBoard(emptyBoard);
     return board.signMap;
   }
 
   // Check cache first
-  const cacheKey = createCacheKey(moves, moveNumber);
+  const cacheKey = createCacheKey(setup, moves, moveNumber);
   const cachedResult = boardPositionCache.get(cacheKey);
   if (cachedResult) {
     return cachedResult;
@@ -84,9 +94,13 @@ export function calculateBoardPosition(
 
   // Calculate the board position
   let board = new GoBoard(emptyBoard);
+  for (const stone of setup) {
+    board = board.set([stone.point.x, stone.point.y], stone.color);
+  }
   for (let i = 0; i <= moveNumber; i++) {
     const move = moves[i];
     if (!move) break;
+    if (isPass(move.point)) continue;
     board = board.makeMove(move.color, [move.point.x, move.point.y]);
   }
 
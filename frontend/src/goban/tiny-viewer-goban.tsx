@@ -2,7 +2,7 @@ import { Goban, type Map, type Marker } from "./shudan";
 import "./shudan/css/goban.css";
 import { useMemo, useState, useCallback } from "react";
 import { emptyBoard } from "@/sabaki-types";
-import { toSabakiMove, type Game } from "@/wasm-search-types";
+import { isPass, toSabakiMove, type Game } from "@/wasm-search-types";
 import { calculateBoardPosition } from "./calculate-board";
 
 import "./viewer-goban.css";
@@ -22,14 +22,15 @@ export default function TinyViewerGoban({
 }: TinyViewerGobanProps) {
   const [isHovering, setIsHovering] = useState(false);
   const board = useMemo(() => {
+    const setup = game.setup_transformed.map(toSabakiMove);
     const moves = game.moves_transformed.map(toSabakiMove);
-    return calculateBoardPosition(moves, moveNumber);
-  }, [game.moves_transformed, moveNumber]);
+    return calculateBoardPosition(setup, moves, moveNumber);
+  }, [game.setup_transformed, game.moves_transformed, moveNumber]);
 
   const markerMap = useMemo((): Map<Marker | null> => {
     const mm: Map<Marker | null> = emptyBoard.map((row) => row.map(() => null));
     const lastMove = game.moves_transformed[moveNumber];
-    if (lastMove !== undefined) {
+    if (lastMove !== undefined && !isPass(lastMove.point)) {
       mm[lastMove.point.y][lastMove.point.x] = {
         type: "circle",
       } as Marker;
@@ -6,7 +6,7 @@ import { useImmer } from "use-immer";
 import { Button } from "@/ui-primitives/button";
 import { Input } from "@/ui-primitives/input";
 import { emptyBoard, type BoardPosition } from "@/sabaki-types";
-import { toSabakiMove, type Game } from "@/wasm-search-types";
+import { isPass, toSabakiMove, type Game } from "@/wasm-search-types";
 import { calculateBoardPosition } from "./calculate-board";
 
 export type GameSelection = {
@@ -60,7 +60,7 @@ const ViewerGoban = forwardRef<ViewerGobanRef, ViewerGobanProps>(
         if (gameSelection) {
           const lastMove =
             gameSelection.game.moves_transformed[gameSelection.moveNumber];
-          if (lastMove !== undefined) {
+          if (lastMove !== undefined && !isPass(lastMove.point)) {
             draft[lastMove.point.y][lastMove.point.x] = {
               type: "circle",
             };
@@ -186,9 +186,12 @@ const ViewerGoban = forwardRef<ViewerGobanRef, ViewerGobanProps>(
     );
 
     useEffect(() => {
+      const setup =
+        gameSelection?.game.setup_transformed.map(toSabakiMove) || [];
       const moves =
         gameSelection?.game.moves_transformed.map(toSabakiMove) || [];
       const newBoard = calculateBoardPosition(
+        setup,
         moves,
         gameSelection?.moveNumber ?? -1,
       );
@@ -25,13 +25,7 @@ if (window.Worker) {
 
 const wasmSearchPostMessage = (message: WasmSearchMessage) => {
   if (wasmSearchWorker) {
-    if (message.type === "search") {
-      wasmSearchWorker.postMessage(message, [
-        message.payload.positionBuf.buffer,
-      ]);
-    } else if (message.type === "getSearchResultByPath") {
-      wasmSearchWorker.postMessage(message);
-    }
+    wasmSearchWorker.postMessage(message);
   }
 };
 
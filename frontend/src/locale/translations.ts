@@ -51,6 +51,8 @@ export const translations = {
     // Tooltips and alt text
     "tooltip.mirrored": "The game is mirrored to match the pattern",
     "tooltip.colorsInverted": "Colors are inverted to match the pattern",
+    "tooltip.variation":
+      "The match was found in a variation, not in the moves played",
     "alt.trophy": "Trophy icon",
     "alt.black": "Black",
     "alt.white": "White",
@@ -71,6 +73,7 @@ export const translations = {
     "info.result": "Result:",
     "info.matched": "Matched:",
     "info.move": "Move",
+    "info.variation": "Variation",
     "info.komi": "Komi:",
     "info.rules": "Rules:",
     "info.sgfSource": "SGF Source:",
@@ -151,6 +154,8 @@ export const translations = {
       "Das Spiel ist gespiegelt, um dem Muster zu entsprechen",
     "tooltip.colorsInverted":
       "Die Farben sind invertiert, um dem Muster zu entsprechen",
+    "tooltip.variation":
+      "Der Treffer stammt aus einer Variante, nicht aus den gespielten Zügen",
     "alt.trophy": "Pokal-Symbol",
     "alt.black": "Schwarz",
     "alt.white": "Weiß",
@@ -171,6 +176,7 @@ export const translations = {
     "info.result": "Ergebnis:",
     "info.matched": "Zum Muster:",
     "info.move": "Zug",
+    "info.variation": "Variante",
     "info.komi": "Komi:",
     "info.rules": "Regeln:",
     "info.sgfSource": "SGF-Quelle:",
@@ -250,6 +256,7 @@ export const translations = {
     // Tooltips and alt text
     "tooltip.mirrored": "棋谱已镜像以匹配模式",
     "tooltip.colorsInverted": "颜色已反转以匹配模式",
+    "tooltip.variation": "匹配来自变化图，而非实战着法",
     "alt.trophy": "奖杯图标",
     "alt.black": "黑棋",
     "alt.white": "白棋",
@@ -270,6 +277,7 @@ export const translations = {
     "info.result": "结果：",
     "info.matched": "匹配：",
     "info.move": "手",
+    "info.variation": "变化",
     "info.komi": "贴目：",
     "info.rules": "规则：",
     "info.sgfSource": "SGF来源：",
@@ -348,6 +356,7 @@ export const translations = {
     // Tooltips and alt text
     "tooltip.mirrored": "パターンにマッチするよう棋譜が反転されています",
     "tooltip.colorsInverted": "パターンにマッチするよう色が反転されています",
+    "tooltip.variation": "実戦の手順ではなく変化図でマッチしました",
     "alt.trophy": "トロフィーアイコン",
     "alt.black": "黒",
     "alt.white": "白",
@@ -368,6 +377,7 @@ export const translations = {
     "info.result": "結果：",
     "info.matched": "マッチ：",
     "info.move": "手",
+    "info.variation": "変化図",
     "info.komi": "コミ：",
     "info.rules": "ルール：",
     "info.sgfSource": "SGFソース：",
@@ -446,6 +456,7 @@ export const translations = {
     // Tooltips and alt text
     "tooltip.mirrored": "패턴에 맞추기 위해 기보가 좌우 반전되었습니다",
     "tooltip.colorsInverted": "패턴에 맞추기 위해 색이 반전되었습니다",
+    "tooltip.variation": "실전 수순이 아닌 변화도에서 찾은 결과입니다",
     "alt.trophy": "트로피 아이콘",
     "alt.black": "흑",
     "alt.white": "백",
@@ -466,6 +477,7 @@ export const translations = {
     "info.result": "결과:",
     "info.matched": "일치:",
     "info.move": "수",
+    "info.variation": "변화도",
     "info.komi": "덤:",
     "info.rules": "규칙:",
     "info.sgfSource": "SGF 소스:",
@@ -544,6 +556,8 @@ export const translations = {
     // Tooltips and alt text
     "tooltip.mirrored": "Партия отражена для соответствия шаблону",
     "tooltip.colorsInverted": "Цвета инвертированы для соответствия шаблону",
+    "tooltip.variation":
+      "Совпадение найдено в варианте, а не в сыгранных ходах",
     "alt.trophy": "Иконка трофея",
     "alt.black": "Чёрные",
     "alt.white": "Белые",
@@ -564,6 +578,7 @@ export const translations = {
     "info.result": "Результат:",
     "info.matched": "Совпадение:",
     "info.move": "Ход",
+    "info.variation": "Вариант",
     "info.komi": "Коми:",
     "info.rules": "Правила:",
     "info.sgfSource": "Источник SGF:",
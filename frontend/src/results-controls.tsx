@@ -3,7 +3,7 @@ import { Label } from "@/ui-primitives/label";
 import { Separator } from "@/ui-primitives/separator";
 import { Toggle } from "@/ui-primitives/toggle";
 import { useCallback } from "react";
-import { SortBy } from "@/../../rust/wasm-search/pkg/wasm_search";
+import type { SortBy } from "@/wasm-search-types";
 import trophyCrossedOutSvg from "./assets/icons/trophy-crossed-out.svg";
 import trophySvg from "./assets/icons/trophy.svg";
 import {
@@ -32,7 +32,7 @@ export default function ResultsControls({
   const { t, tc } = useTranslations();
 
   const handleSortByChange = useCallback(
-    (s: string) => onSortByChange(parseInt(s, 10)),
+    (s: string) => onSortByChange(s as SortBy),
     [onSortByChange],
   );
 
@@ -40,15 +40,15 @@ export default function ResultsControls({
     <div className="flex space-x-3 font-medium flex-wrap justify-center flex-col items-center lg:justify-end xl:flex-row">
       <div className="flex items-center gap-2 mb-2">
         <div className="text-gray-500 font-normal">{t("sort.label")}</div>
-        <Select value={`${sortResultsBy}`} onValueChange={handleSortByChange}>
+        <Select value={sortResultsBy} onValueChange={handleSortByChange}>
           <SelectTrigger className="w-[225px]">
             <SelectValue placeholder={t("sort.placeholder")} />
           </SelectTrigger>
           <SelectContent>
-            <SelectItem value={`${SortBy.BestMatch}`}>
+            <SelectItem value={"BestMatch" satisfies SortBy}>
               {t("sort.bestMatch")}
             </SelectItem>
-            <SelectItem value={`${SortBy.LeastMoves}`}>
+            <SelectItem value={"LeastMoves" satisfies SortBy}>
               {t("sort.leastMoves")}
             </SelectItem>
           </SelectContent>
@@ -1,6 +1,5 @@
 import { type BoardPosition, emptyBoard, SabakiSign } from "@/sabaki-types";
-import { type PlayerFilter } from "@/wasm-search-types";
-import { SortBy } from "../../rust/wasm-search/pkg/wasm_search";
+import { type PlayerFilter, type SortBy } from "@/wasm-search-types";
 
 const GO_BOARD_COLUMNS = "ABCDEFGHJKLMNOPQRST"; // (no I)
 /**
@@ -220,7 +219,7 @@ export function updateUrlParams(
     urlParams.set("players", serialized);
   }
 
-  if (sortBy === SortBy.BestMatch) {
+  if (sortBy === "BestMatch") {
     urlParams.delete("sort_by");
   } else {
     urlParams.set("sort_by", "least_moves");
@@ -245,7 +244,7 @@ export type GameFromUrl = {
 export function getSelectedGameFromUrl(): GameFromUrl | null {
   const urlParams = new URLSearchParams(window.location.search);
   const path = window.location.pathname.startsWith("/game/")
-    ? window.location.pathname.replace(/^\/game\//, "")
+    ? window.location.pathname.replace(/^\/game\//, "").replace(/%23/g, "#")
     : null;
   const rotation = parseInt(urlParams.get("rotation") || "0", 10);
   const isMirrored = urlParams.get("mirrored") === "1";
@@ -284,7 +283,8 @@ export function updateUrlWithSelectedGame(
 
   let newPathname = "/";
   if (path) {
-    newPathname = `/game/${path}`;
+    // games of SGF collections have paths like `file#2`, which must not become a fragment
+    newPathname = `/game/${path.replace(/#/g, "%23")}`;
 
     // Set query parameters when a game is selected
     urlParams.set("rotation", rotation.toString());
@@ -314,7 +314,7 @@ export function getSortByFromUrl(): SortBy {
   const urlParams = new URLSearchParams(window.location.search);
   const sortByParam = urlParams.get("sort_by");
   if (sortByParam === "least_moves") {
-    return SortBy.LeastMoves;
+    return "LeastMoves";
   }
-  return SortBy.BestMatch;
+  return "BestMatch";
 }
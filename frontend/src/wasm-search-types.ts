@@ -3,96 +3,54 @@ import {
   type BoardPosition,
   type SabakiMove,
 } from "@/sabaki-types";
-import type { SortBy } from "../../rust/wasm-search/pkg/wasm_search";
-
-export type Point = {
-  x: number;
-  y: number;
-};
-
-export type Placement = {
-  color: "Black" | "White";
-  point: Point;
-};
-
-export type PlayerFilter = {
-  player_id: number;
-  color: "Black" | "White" | null;
-};
-
-export type SgfDate = {
-  YearMonthDay?: [number, number, number];
-  YearMonth?: [number, number];
-  Year?: number;
-  Custom?: string;
-};
-
-export type Score = "Resignation" | "Timeout" | "Forfeit" | { Points: number };
-
-export type GameResult =
-  | "Draw"
-  | "Void"
-  | { Unknown: string; Player: undefined; Points: undefined }
-  | {
-      Unknown: undefined;
-      Player: [string, Score | null, string];
-      Points: number;
-    };
-
-export type Rank = {
-  Kyu?: number;
-  Dan?: number;
-  Pro?: number;
-  Custom?: string;
-};
-
-export type Rules = {
-  Chinese?: boolean;
-  Japanese?: boolean;
-  Korean?: boolean;
-  Ing?: boolean;
-  Custom?: string;
-};
-
-export type Player = {
-  Id?: [number, string];
-  Unknown?: string;
-};
-
-export type Game = {
-  path: string;
-  score: number;
-  last_move_matched: number;
-  rotation: number; // 0: no rotation, 1-3: rotation index
-  is_inverted: boolean; // whether the pattern colors were inverted
-  is_mirrored: boolean; // whether the pattern was mirrored
-  all_empty_correctly_within: number; // distance from moves where all surrounding points are correctly empty
-  moves: Placement[]; // game moves
-  moves_transformed: Placement[]; // games moves rotated and/or mirrored
-  // Game metadata
-  event: string;
-  round: string;
-  location: string;
-  date: SgfDate | null;
-  player_black: Player;
-  player_white: Player;
-  rank_black: Rank;
-  rank_white: Rank;
-  komi: number | null;
-  rules: Rules | null;
-  result: GameResult;
-};
+import type { Placement } from "@/bindings/Placement";
+import type { Point } from "@/bindings/Point";
+import type { SearchRequest } from "@/bindings/SearchRequest";
+import type { SearchResult } from "@/bindings/SearchResult";
+
+// generated from the Rust types by `yarn bindings`, see src/bindings
+export type { Color } from "@/bindings/Color";
+export type { GameResult } from "@/bindings/GameResult";
+export type { MatchedStone } from "@/bindings/MatchedStone";
+export type { NextMove } from "@/bindings/NextMove";
+export type { PackMetadata } from "@/bindings/PackMetadata";
+export type { PatternFilter } from "@/bindings/PatternFilter";
+export type { Placement } from "@/bindings/Placement";
+export type { Player } from "@/bindings/Player";
+export type { PlayerFilter } from "@/bindings/PlayerFilter";
+export type { Point } from "@/bindings/Point";
+export type { Rank } from "@/bindings/Rank";
+export type { Rules } from "@/bindings/Rules";
+export type { Score } from "@/bindings/Score";
+export type { ScoreBreakdown } from "@/bindings/ScoreBreakdown";
+export type { ScoreWeights } from "@/bindings/ScoreWeights";
+export type { SearchRequest } from "@/bindings/SearchRequest";
+export type { SearchResponse } from "@/bindings/SearchResponse";
+export type { SgfDate } from "@/bindings/SgfDate";
+export type { SortBy } from "@/bindings/SortBy";
+
+export type Game = SearchResult;
 
 export const emptyGame: Game = {
   path: "",
   score: 0,
-  last_move_matched: 0,
+  score_breakdown: {
+    transform: 0,
+    empty_surrounding: 0,
+    empty_multiplier: 0,
+    move_penalty: 0,
+  },
+  last_move_matched: null,
   rotation: 0,
   is_inverted: false,
   is_mirrored: false,
   all_empty_correctly_within: 0,
+  matched_stones: [],
+  setup: [],
+  setup_transformed: [],
   moves: [],
   moves_transformed: [],
+  variation: null,
   event: "",
   round: "",
   location: "",
@@ -111,25 +69,12 @@ export const emptyGame: Game = {
   },
   result: {
     Unknown: "",
-    Player: undefined,
-    Points: undefined,
   },
   komi: null,
   rules: null,
-};
-
-export type NextMove = {
-  point: { x: number; y: number };
-  game_count: number;
-};
-
-export type SearchReturn = {
-  num_results: number;
-  next_moves: Array<NextMove>;
-  results: Array<Game>;
-  total_pages: number;
-  current_page: number;
-  player_counts: Record<number, number>; // player_id -> count of games
+  board_size: 19,
+  handicap: 0,
+  first_color: "Black",
 };
 
 export function toWasmSearch(board: BoardPosition): Array<Placement> {
@@ -146,6 +91,13 @@ export function toWasmSearch(board: BoardPosition): Array<Placement> {
   return position;
 }
 
+// the point of a pass, `Point::PASS` in Rust
+export const PASS: Point = { x: 19, y: 19 };
+
+export function isPass(point: Point): boolean {
+  return point.x === PASS.x && point.y === PASS.y;
+}
+
 export function toSabakiMove(move: Placement): SabakiMove {
   return {
     color: move.color === "Black" ? SabakiSign.Black : SabakiSign.White,
@@ -156,14 +108,7 @@ export function toSabakiMove(move: Placement): SabakiMove {
 export type WasmSearchMessage =
   | {
       type: "search";
-      payload: {
-        positionBuf: Uint8Array;
-        nextColor: number;
-        page: number;
-        pageSize: number;
-        playerFilters: PlayerFilter[];
-        sortBy: SortBy;
-      };
+      payload: SearchRequest;
     }
   | {
       type: "getSearchResultByPath";
@@ -1,18 +1,10 @@
 import initWasm, {
   WasmSearch,
-  SortBy,
 } from "../../rust/wasm-search/pkg/wasm_search.js";
-import type { PlayerFilter } from "@/wasm-search-types.js";
+import type { SearchRequest } from "@/wasm-search-types.js";
 
 let wasmInitialized = false;
-let queue: Array<{
-  positionBuf: Uint8Array;
-  nextColor: number;
-  page: number;
-  pageSize: number;
-  playerFilters: PlayerFilter[];
-  sortBy: SortBy;
-}> = [];
+let queue: Array<SearchRequest> = [];
 let isSearching = false;
 
 onmessage = async (e) => {
@@ -31,10 +23,7 @@ onmessage = async (e) => {
       rotation,
       isMirrored,
     );
-    //@ts-expect-error postMessage for the worker doesn't have the correct type definition
-    self.postMessage({ type: "searchResultByPath", payload: result }, [
-      result.buffer,
-    ]);
+    self.postMessage({ type: "searchResultByPath", payload: result });
   }
 };
 
@@ -50,32 +39,16 @@ async function handleQueue() {
   if (queue.length > 0 && !isSearching && wasmInitialized) {
     isSearching = true;
     // take the latest query and discard the rest
-    const {
-      positionBuf,
-      nextColor,
-      page = 0,
-      pageSize = 10,
-      playerFilters = [],
-      sortBy = SortBy.BestMatch,
-    } = queue.pop()!;
+    const request = queue.pop()!;
     queue = [];
-    const playerFiltersJson = new TextEncoder().encode(
-      JSON.stringify(playerFilters),
-    );
-    const results = await wasmSearch.search(
-      positionBuf,
-      nextColor,
-      page,
-      pageSize,
-      new Uint8Array(playerFiltersJson),
-      sortBy,
-    );
+    // the compact encoding is much faster to transfer than structured results
+    const response = await wasmSearch.search_binary(request);
     // give the JS event loop a chance to add queries to the queue
     await new Promise((resolve) => setTimeout(resolve, 0));
     // if there are no new queries, send this result
     if (queue.length === 0) {
       //@ts-expect-error postMessage for the worker doesn't have the correct type definition
-      self.postMessage({ type: "result", payload: results }, [results.buffer]);
+      self.postMessage({ type: "result", payload: response }, [response.buffer]);
     }
     isSearching = false;
   }
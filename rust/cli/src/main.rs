@@ -0,0 +1,280 @@
+use calm_go_patterns_common::archive::MappedArchive;
+use calm_go_patterns_common::baduk::{BOARD_SIZE, Color, Player, read_pack};
+use calm_go_patterns_common::sgf::{format_date, format_result};
+use calm_go_patterns_common::store::GameStore;
+use calm_go_patterns_search::export::{write_csv, write_jsonl};
+use calm_go_patterns_search::{
+    PatternFilter, PlayerFilter, ScoreWeights, SearchEngine, SearchRequest, SearchResponse,
+    SearchResult, SortBy,
+};
+use clap::{Parser, ValueEnum};
+use std::io::Read;
+use std::path::PathBuf;
+
+mod query;
+use query::{QueryFormat, detect_format, parse_query};
+
+/// Search a games pack for a position
+#[derive(Parser)]
+#[command(name = "search")]
+struct Args {
+    /// The query position, read from stdin when left out or `-`
+    query: Option<String>,
+
+    /// Format of the query, guessed from its contents when left out
+    #[arg(long, value_enum)]
+    query_format: Option<QueryFormat>,
+
+    /// A games.pack written by pack-games, or a games.archive
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/../wasm-search/src/games.pack"))]
+    pack: PathBuf,
+
+    /// Color of the next move, defaults to the opposite of the last query stone
+    #[arg(long, value_enum)]
+    next_color: Option<ColorArg>,
+
+    /// Only games on boards of this size are searched, the query is placed on this board
+    #[arg(long, default_value_t = BOARD_SIZE, value_parser = parse_board_size)]
+    board_size: u8,
+
+    #[arg(long, value_enum, default_value_t = SortArg::BestMatch)]
+    sort: SortArg,
+
+    /// Player that must have played in the game, as `ID`, `ID:black` or `ID:white`
+    #[arg(long = "player", value_parser = parse_player_filter)]
+    players: Vec<PlayerFilter>,
+
+    /// JSON-encoded PatternFilter, e.g. `{"ColorWon": {"color": "Black"}}`
+    #[arg(long = "pattern-filter", value_parser = parse_pattern_filter)]
+    pattern_filters: Vec<PatternFilter>,
+
+    /// JSON-encoded ScoreWeights, any fields left out use their default
+    #[arg(long, value_parser = parse_score_weights)]
+    weights: Option<ScoreWeights>,
+
+    /// Page number (0-based)
+    #[arg(long, default_value_t = 0)]
+    page: usize,
+
+    #[arg(long, default_value_t = 10, value_parser = parse_page_size)]
+    page_size: usize,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorArg {
+    Black,
+    White,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SortArg {
+    BestMatch,
+    LeastMoves,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// One line per result with its score, players and path
+    Table,
+    /// The full search response
+    Json,
+    /// Only the paths of the matched games
+    Paths,
+    /// Every result with its game, one row each, ignoring the page options
+    Csv,
+    /// Like `csv`, as one JSON object per line
+    Jsonl,
+}
+
+fn parse_player_filter(arg: &str) -> Result<PlayerFilter, String> {
+    let (id, color) = match arg.split_once(':') {
+        Some((id, color)) => (id, Some(color)),
+        None => (arg, None),
+    };
+    let color = match color.map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("black" | "b") => Some(Color::Black),
+        Some("white" | "w") => Some(Color::White),
+        Some(color) => return Err(format!("Unknown color '{color}'")),
+    };
+    Ok(PlayerFilter {
+        player_id: id
+            .parse()
+            .map_err(|e| format!("Invalid player ID '{id}': {e}"))?,
+        color,
+    })
+}
+
+fn parse_page_size(arg: &str) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(0) => Err("Page size must be at least 1".to_string()),
+        Ok(page_size) => Ok(page_size),
+        Err(e) => Err(format!("Invalid page size '{arg}': {e}")),
+    }
+}
+
+fn parse_board_size(arg: &str) -> Result<u8, String> {
+    match arg.parse() {
+        Ok(board_size) if (2..=BOARD_SIZE).contains(&board_size) => Ok(board_size),
+        Ok(_) => Err(format!("Board size must be from 2 to {BOARD_SIZE}")),
+        Err(e) => Err(format!("Invalid board size '{arg}': {e}")),
+    }
+}
+
+fn parse_pattern_filter(arg: &str) -> Result<PatternFilter, String> {
+    serde_json::from_str(arg).map_err(|e| format!("Invalid pattern filter: {e}"))
+}
+
+fn parse_score_weights(arg: &str) -> Result<ScoreWeights, String> {
+    serde_json::from_str(arg).map_err(|e| format!("Invalid score weights: {e}"))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
+
+fn player_name(player: &Player) -> &str {
+    match player {
+        Player::Id(_, name) | Player::Unknown(name) => name,
+    }
+}
+
+fn format_transform(result: &SearchResult) -> String {
+    let mut transform = match result.rotation {
+        1 => "90°".to_string(),
+        2 => "180°".to_string(),
+        3 => "270°".to_string(),
+        _ => String::new(),
+    };
+    if result.is_mirrored {
+        transform.push_str(" mirrored");
+    }
+    if result.is_inverted {
+        transform.push_str(" inverted");
+    }
+    transform.trim().to_string()
+}
+
+fn print_response(response: &SearchResponse, page_size: usize, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(response).expect("Failed to serialize results")
+        ),
+        OutputFormat::Paths => {
+            for result in &response.results {
+                println!("{}", result.path);
+            }
+        }
+        OutputFormat::Csv => write_csv(&response.results, &mut std::io::stdout().lock())
+            .unwrap_or_else(|e| fail(&format!("Failed to write results: {e}"))),
+        OutputFormat::Jsonl => write_jsonl(&response.results, &mut std::io::stdout().lock())
+            .unwrap_or_else(|e| fail(&format!("Failed to write results: {e}"))),
+        OutputFormat::Table => {
+            println!(
+                "{:>4}  {:>6}  {:>5}  {:<18}  {:<20}  {:<20}  {:<7}  {:<10}  path",
+                "#", "score", "move", "transform", "black", "white", "result", "date"
+            );
+            let first = response.current_page * page_size;
+            for (i, result) in response.results.iter().enumerate() {
+                println!(
+                    "{:>4}  {:>6}  {:>5}  {:<18}  {:<20}  {:<20}  {:<7}  {:<10}  {}",
+                    first + i + 1,
+                    result.score,
+                    result.last_move_matched.map_or(0, |m| m + 1),
+                    format_transform(result),
+                    player_name(&result.player_black),
+                    player_name(&result.player_white),
+                    format_result(&result.result),
+                    result.date.as_ref().map(format_date).unwrap_or_default(),
+                    result.path
+                );
+            }
+            println!(
+                "{} games, page {} of {}",
+                response.num_results,
+                response.current_page + 1,
+                response.total_pages.max(1)
+            );
+        }
+    }
+}
+
+fn run<S: GameStore>(games: S, request: &SearchRequest, args: &Args) {
+    let mut engine = SearchEngine::new(games);
+    if let Some(weights) = &args.weights {
+        engine.set_score_weights(weights.clone());
+    }
+    let response = engine
+        .search(request)
+        .unwrap_or_else(|e| fail(&format!("Invalid search: {e}")));
+    print_response(&response, request.page_size, args.format);
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let query = match args.query.as_deref() {
+        Some(query) if query != "-" => query.to_string(),
+        _ => {
+            let mut query = String::new();
+            std::io::stdin()
+                .read_to_string(&mut query)
+                .unwrap_or_else(|e| fail(&format!("Failed to read query: {e}")));
+            query
+        }
+    };
+    let query_format = args.query_format.unwrap_or_else(|| detect_format(&query));
+    let position = parse_query(&query, query_format, args.board_size).unwrap_or_else(|e| fail(&e));
+
+    let next_color = match args.next_color {
+        Some(ColorArg::Black) => Color::Black,
+        Some(ColorArg::White) => Color::White,
+        None => match position.last() {
+            Some(placement) if placement.color == Color::Black => Color::White,
+            _ => Color::Black,
+        },
+    };
+    // the exports hold all results on one page
+    let is_export = matches!(args.format, OutputFormat::Csv | OutputFormat::Jsonl);
+    let request = SearchRequest {
+        position,
+        next_color,
+        page: if is_export { 0 } else { args.page },
+        page_size: if is_export {
+            usize::MAX
+        } else {
+            args.page_size
+        },
+        player_filters: args.players.clone(),
+        pattern_filters: args.pattern_filters.clone(),
+        sort_by: match args.sort {
+            SortArg::BestMatch => SortBy::BestMatch,
+            SortArg::LeastMoves => SortBy::LeastMoves,
+        },
+        board_size: args.board_size,
+    };
+
+    let read_error = |e: &dyn std::fmt::Display| -> ! {
+        fail(&format!("Failed to read {}: {e}", args.pack.display()))
+    };
+    // archives are memory-mapped, packs have to be decoded as a whole
+    let mut magic = [0u8; 4];
+    let is_archive = std::fs::File::open(&args.pack)
+        .and_then(|mut file| file.read(&mut magic))
+        .unwrap_or_else(|e| read_error(&e))
+        == magic.len()
+        && &magic == b"CGPA";
+    if is_archive {
+        let archive = MappedArchive::open(&args.pack).unwrap_or_else(|e| read_error(&e));
+        run(archive, &request, &args);
+    } else {
+        let bytes = std::fs::read(&args.pack).unwrap_or_else(|e| read_error(&e));
+        let (_, games) = read_pack(&bytes).unwrap_or_else(|e| read_error(&e));
+        run(games, &request, &args);
+    }
+}
@@ -0,0 +1,225 @@
+use calm_go_patterns_common::baduk::{BOARD_SIZE, Color, Placement};
+use calm_go_patterns_common::coords::{parse_diagram, parse_gtp_point, parse_sgf_point};
+use clap::ValueEnum;
+
+/// The ways a query position can be written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QueryFormat {
+    /// A JSON array of placements, `[{"color": "Black", "point": {"x": 3, "y": 3}}]`
+    Json,
+    /// SGF move and setup properties, `B[dd];W[pp]` or `AB[dd][dp]AW[pd]`
+    Sgf,
+    /// GTP vertices, alternating colors starting with black unless preceded by a color,
+    /// `Q16 D4` or `B Q16 B D4 W C6`
+    Gtp,
+    /// A diagram in the style of Sensei's Library, `X` black, `O` white and `.` empty,
+    /// placed at the top left unless its borders say otherwise
+    Ascii,
+}
+
+/// Guesses the format of a query from its first characters
+pub fn detect_format(query: &str) -> QueryFormat {
+    let query = query.trim_start();
+    if query.starts_with('[') {
+        QueryFormat::Json
+    } else if query.contains('[') {
+        QueryFormat::Sgf
+    } else if query
+        .split_whitespace()
+        .all(|token| parse_gtp_color(token).is_some() || parse_gtp_point(token, BOARD_SIZE).is_ok())
+    {
+        QueryFormat::Gtp
+    } else {
+        QueryFormat::Ascii
+    }
+}
+
+/// Parses a query position on a board of `board_size`
+pub fn parse_query(
+    query: &str,
+    format: QueryFormat,
+    board_size: u8,
+) -> Result<Vec<Placement>, String> {
+    match format {
+        QueryFormat::Json => {
+            let position: Vec<Placement> =
+                serde_json::from_str(query).map_err(|e| format!("Invalid JSON query: {e}"))?;
+            match position
+                .iter()
+                .find(|p| p.point.x >= board_size || p.point.y >= board_size)
+            {
+                Some(p) => Err(format!(
+                    "Invalid JSON query: {:?} is outside the {board_size}x{board_size} board",
+                    p.point
+                )),
+                None => Ok(position),
+            }
+        }
+        QueryFormat::Sgf => parse_sgf_query(query, board_size),
+        QueryFormat::Gtp => parse_gtp_query(query, board_size),
+        QueryFormat::Ascii => parse_diagram(query, board_size).map_err(|e| e.to_string()),
+    }
+}
+
+fn parse_sgf_query(query: &str, board_size: u8) -> Result<Vec<Placement>, String> {
+    let mut placements = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || matches!(c, ';' | '(' | ')') {
+            chars.next();
+            continue;
+        }
+        if !c.is_ascii_uppercase() {
+            return Err(format!("Unexpected '{c}' in SGF query"));
+        }
+
+        let mut identifier = String::new();
+        while let Some(&c) = chars.peek().filter(|c| c.is_ascii_uppercase()) {
+            identifier.push(c);
+            chars.next();
+        }
+        let color = match identifier.as_str() {
+            "B" | "AB" => Color::Black,
+            "W" | "AW" => Color::White,
+            _ => return Err(format!("Unsupported SGF property '{identifier}'")),
+        };
+
+        let mut values = 0;
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        while chars.next_if_eq(&'[').is_some() {
+            let value: String = chars.by_ref().take_while(|&c| c != ']').collect();
+            placements.push(Placement {
+                color,
+                point: parse_sgf_point(value.trim(), board_size)
+                    .map_err(|e| format!("Invalid SGF query: {e}"))?,
+            });
+            values += 1;
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+        }
+        if values == 0 {
+            return Err(format!("SGF property '{identifier}' has no value"));
+        }
+    }
+    Ok(placements)
+}
+
+fn parse_gtp_color(token: &str) -> Option<Color> {
+    match token.to_lowercase().as_str() {
+        "b" | "black" => Some(Color::Black),
+        "w" | "white" => Some(Color::White),
+        _ => None,
+    }
+}
+
+fn parse_gtp_query(query: &str, board_size: u8) -> Result<Vec<Placement>, String> {
+    let mut placements: Vec<Placement> = Vec::new();
+    let mut color = None;
+    for token in query.split(|c: char| c.is_whitespace() || c == ',') {
+        if token.is_empty() {
+            continue;
+        }
+        if let Some(token_color) = parse_gtp_color(token) {
+            color = Some(token_color);
+            continue;
+        }
+        let point =
+            parse_gtp_point(token, board_size).map_err(|e| format!("Invalid GTP query: {e}"))?;
+        // without a color the moves alternate
+        let color = color.take().unwrap_or(match placements.last() {
+            Some(placement) if placement.color == Color::Black => Color::White,
+            _ => Color::Black,
+        });
+        placements.push(Placement { color, point });
+    }
+    Ok(placements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calm_go_patterns_common::baduk::Point;
+
+    fn placement(color: Color, x: u8, y: u8) -> Placement {
+        Placement {
+            color,
+            point: Point { x, y },
+        }
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let expected = vec![placement(Color::Black, 3, 3), placement(Color::White, 2, 5)];
+
+        let json = r#"[{"color": "Black", "point": {"x": 3, "y": 3}},
+                       {"color": "White", "point": {"x": 2, "y": 5}}]"#;
+        assert_eq!(detect_format(json), QueryFormat::Json);
+        assert_eq!(
+            parse_query(json, QueryFormat::Json, BOARD_SIZE),
+            Ok(expected.clone())
+        );
+
+        let sgf = "(;B[dd];W[cf])";
+        assert_eq!(detect_format(sgf), QueryFormat::Sgf);
+        assert_eq!(
+            parse_query(sgf, QueryFormat::Sgf, BOARD_SIZE),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            parse_query("AB[dd] [ee] AW[cf]", QueryFormat::Sgf, BOARD_SIZE),
+            Ok(vec![
+                placement(Color::Black, 3, 3),
+                placement(Color::Black, 4, 4),
+                placement(Color::White, 2, 5),
+            ])
+        );
+        assert!(parse_query("B[zz]", QueryFormat::Sgf, BOARD_SIZE).is_err());
+
+        let gtp = "D16 C14";
+        assert_eq!(detect_format(gtp), QueryFormat::Gtp);
+        assert_eq!(
+            parse_query(gtp, QueryFormat::Gtp, BOARD_SIZE),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            parse_query("b D16 black E15, W C14", QueryFormat::Gtp, BOARD_SIZE),
+            Ok(vec![
+                placement(Color::Black, 3, 3),
+                placement(Color::Black, 4, 4),
+                placement(Color::White, 2, 5),
+            ])
+        );
+        assert!(parse_query("D16 I5", QueryFormat::Gtp, BOARD_SIZE).is_err());
+        assert!(parse_query("C[comment]", QueryFormat::Sgf, BOARD_SIZE).is_err());
+
+        let ascii = "
+            . . . . .
+            . . . . .
+            . . . . .
+            . . . X .
+            . . . . .
+            . . O . .
+        ";
+        assert_eq!(detect_format(ascii), QueryFormat::Ascii);
+        assert_eq!(
+            parse_query(ascii, QueryFormat::Ascii, BOARD_SIZE),
+            Ok(expected)
+        );
+        assert!(parse_query("..X?", QueryFormat::Ascii, BOARD_SIZE).is_err());
+
+        // on a 9x9 board the rows are numbered from its own bottom edge
+        assert_eq!(
+            parse_query("C6 D5", QueryFormat::Gtp, 9),
+            Ok(vec![
+                placement(Color::Black, 2, 3),
+                placement(Color::White, 3, 4)
+            ])
+        );
+        assert!(parse_query("D16", QueryFormat::Gtp, 9).is_err());
+        assert!(parse_query("B[jj]", QueryFormat::Sgf, 9).is_err());
+        assert!(parse_query(json, QueryFormat::Json, 3).is_err());
+    }
+}
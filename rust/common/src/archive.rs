@@ -0,0 +1,556 @@
+//! A pack layout that can be read in place, for native tools that memory-map their games
+//!
+//! ```text
+//! header   magic "CGPA", u16 version, u16 reserved, u32 game count, u32 metadata length,
+//!          u32 crc32 of everything after the header (all little endian)
+//! metadata MessagePack `PackMetadata`
+//! offsets  game count + 1 u32 offsets of the records, relative to the first record
+//! records  u16 path length, path, game summary, u32 moves length, packed moves,
+//!          u32 captures length, packed captures, MessagePack game metadata up to the next record
+//! summary  u8 board size, u8 flags, u8 winner, u8 reserved, i16 black player ID,
+//!          i16 white player ID
+//! ```
+//!
+//! Opening an archive only checks the header, checksum, record bounds and packed moves and
+//! captures, and indexes the paths. A game's metadata is only deserialized when the game itself is requested, searches
+//! read what they check for every game from the fixed size summary.
+
+use indexmap::IndexMap;
+use rmp_serde::{Deserializer, Serializer};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::baduk::{
+    Color, CompactGame, Game, GameInfo, PackError, PackMetadata, PackedPlacements, Placement,
+    packed_captures_len, packed_placements_len, read_pack, unpack_captures, unpack_placements,
+};
+use crate::store::{GameStore, GameSummary};
+
+const ARCHIVE_MAGIC: &[u8; 4] = b"CGPA";
+/// Version written by `write_archive`, archives of other versions must be converted again from
+/// their pack with `pack_to_archive`
+///
+/// * 1 - the layout above without game summaries
+/// * 2 - like 1, with the board size, handicap, first color, setup stones and variations in the
+///   game metadata, and passes among the moves
+/// * 3 - like 2, with a game summary in each record
+pub const ARCHIVE_VERSION: u16 = 3;
+const ARCHIVE_HEADER_LEN: usize = ARCHIVE_MAGIC.len() + 2 + 2 + 4 + 4 + 4;
+const SUMMARY_LEN: usize = 8;
+
+// flags of a game summary
+const HAS_SETUP: u8 = 1;
+const HAS_VARIATIONS: u8 = 1 << 1;
+const HAS_PLAYER_BLACK: u8 = 1 << 2;
+const HAS_PLAYER_WHITE: u8 = 1 << 3;
+
+/// Fields of a record, borrowed from the archive
+struct Record<'a> {
+    path: &'a [u8],
+    summary: &'a [u8],
+    moves: &'a [u8],
+    captures: &'a [u8],
+    info: &'a [u8],
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn split_record(record: &[u8]) -> Option<Record<'_>> {
+    let path_len = read_u16(record, 0)? as usize;
+    let path = record.get(2..2 + path_len)?;
+    let mut at = 2 + path_len;
+    let summary = record.get(at..at + SUMMARY_LEN)?;
+    at += SUMMARY_LEN;
+    let moves_len = read_u32(record, at)? as usize;
+    let moves = record.get(at + 4..at + 4 + moves_len)?;
+    at += 4 + moves_len;
+    let captures_len = read_u32(record, at)? as usize;
+    let captures = record.get(at + 4..at + 4 + captures_len)?;
+    at += 4 + captures_len;
+    Some(Record {
+        path,
+        summary,
+        moves,
+        captures,
+        info: &record[at..],
+    })
+}
+
+fn write_summary(summary: &GameSummary, buf: &mut Vec<u8>) {
+    let mut flags = 0;
+    for (flag, is_set) in [
+        (HAS_SETUP, summary.has_setup),
+        (HAS_VARIATIONS, summary.has_variations),
+        (HAS_PLAYER_BLACK, summary.player_black.is_some()),
+        (HAS_PLAYER_WHITE, summary.player_white.is_some()),
+    ] {
+        if is_set {
+            flags |= flag;
+        }
+    }
+    let winner = match summary.winner {
+        None => 0u8,
+        Some(Color::Black) => 1,
+        Some(Color::White) => 2,
+    };
+    buf.extend([summary.board_size, flags, winner, 0]);
+    buf.extend(summary.player_black.unwrap_or(0).to_le_bytes());
+    buf.extend(summary.player_white.unwrap_or(0).to_le_bytes());
+}
+
+fn read_summary(bytes: &[u8]) -> GameSummary {
+    let flags = bytes[1];
+    let player_id = |flag: u8, at: usize| {
+        (flags & flag != 0).then(|| i16::from_le_bytes([bytes[at], bytes[at + 1]]))
+    };
+    GameSummary {
+        board_size: bytes[0],
+        has_setup: flags & HAS_SETUP != 0,
+        has_variations: flags & HAS_VARIATIONS != 0,
+        player_black: player_id(HAS_PLAYER_BLACK, 4),
+        player_white: player_id(HAS_PLAYER_WHITE, 6),
+        winner: match bytes[2] {
+            1 => Some(Color::Black),
+            2 => Some(Color::White),
+            _ => None,
+        },
+    }
+}
+
+/// Positions of the sections of a checked archive, with the index of each path
+#[derive(Debug, Clone)]
+struct Layout {
+    metadata: PackMetadata,
+    game_count: usize,
+    offsets: Range<usize>,
+    records: Range<usize>,
+    path_indices: HashMap<String, usize>,
+}
+
+fn record_bounds(offsets: &[u8], index: usize) -> (usize, usize) {
+    let offset = |i: usize| read_u32(offsets, i * 4).expect("Offset out of range");
+    (offset(index) as usize, offset(index + 1) as usize)
+}
+
+fn read_layout(bytes: &[u8]) -> Result<Layout, PackError> {
+    if !bytes.starts_with(ARCHIVE_MAGIC) {
+        return Err(PackError::NotAnArchive);
+    }
+    if bytes.len() < ARCHIVE_HEADER_LEN {
+        return Err(PackError::Truncated);
+    }
+    let version = read_u16(bytes, 4).ok_or(PackError::Truncated)?;
+    if version != ARCHIVE_VERSION {
+        return Err(PackError::UnsupportedVersion(version));
+    }
+    let game_count = read_u32(bytes, 8).ok_or(PackError::Truncated)? as usize;
+    let metadata_len = read_u32(bytes, 12).ok_or(PackError::Truncated)? as usize;
+    let expected = read_u32(bytes, 16).ok_or(PackError::Truncated)?;
+    let actual = crc32fast::hash(&bytes[ARCHIVE_HEADER_LEN..]);
+    if expected != actual {
+        return Err(PackError::ChecksumMismatch { expected, actual });
+    }
+
+    let metadata_bytes = bytes
+        .get(ARCHIVE_HEADER_LEN..ARCHIVE_HEADER_LEN + metadata_len)
+        .ok_or(PackError::Truncated)?;
+    let metadata = PackMetadata::deserialize(&mut Deserializer::new(metadata_bytes))?;
+    if metadata.game_count != game_count {
+        return Err(PackError::GameCountMismatch {
+            expected: metadata.game_count,
+            actual: game_count,
+        });
+    }
+
+    let offsets_start = ARCHIVE_HEADER_LEN + metadata_len;
+    let records_start = offsets_start + (game_count + 1) * 4;
+    if bytes.len() < records_start {
+        return Err(PackError::Truncated);
+    }
+
+    // check the bounds of every record and its packed moves and captures once, so that reading
+    // them afterwards can't go out of bounds, the game metadata is covered by the checksum only
+    let offsets = &bytes[offsets_start..records_start];
+    let records = &bytes[records_start..];
+    let mut path_indices = HashMap::with_capacity(game_count);
+    for index in 0..game_count {
+        let (start, end) = record_bounds(offsets, index);
+        if start > end || end > records.len() {
+            return Err(PackError::InvalidReference(index as u32));
+        }
+        let record =
+            split_record(&records[start..end]).ok_or(PackError::InvalidReference(index as u32))?;
+        let path = std::str::from_utf8(record.path)
+            .map_err(|_| PackError::InvalidReference(index as u32))?;
+        if packed_placements_len(record.moves).is_none()
+            || packed_captures_len(record.captures).is_none()
+        {
+            return Err(PackError::Truncated);
+        }
+        // the first game with a path is the one found by it
+        path_indices.entry(path.to_string()).or_insert(index);
+    }
+
+    Ok(Layout {
+        metadata,
+        game_count,
+        offsets: offsets_start..records_start,
+        records: records_start..bytes.len(),
+        path_indices,
+    })
+}
+
+/// Games read in place from the bytes of `write_archive` output
+#[derive(Debug, Clone)]
+pub struct Archive<'a> {
+    layout: Cow<'a, Layout>,
+    offsets: &'a [u8],
+    records: &'a [u8],
+}
+
+impl<'a> Archive<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Archive<'a>, PackError> {
+        let layout = read_layout(bytes)?;
+        Ok(Archive::from_layout(bytes, Cow::Owned(layout)))
+    }
+
+    fn from_layout(bytes: &'a [u8], layout: Cow<'a, Layout>) -> Archive<'a> {
+        Archive {
+            offsets: &bytes[layout.offsets.clone()],
+            records: &bytes[layout.records.clone()],
+            layout,
+        }
+    }
+
+    pub fn metadata(&self) -> &PackMetadata {
+        &self.layout.metadata
+    }
+
+    fn record(&self, index: usize) -> Record<'a> {
+        assert!(index < self.layout.game_count, "Game index out of range");
+        let (start, end) = record_bounds(self.offsets, index);
+        split_record(&self.records[start..end]).expect("Archive records are checked on open")
+    }
+
+    pub fn len(&self) -> usize {
+        self.layout.game_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layout.game_count == 0
+    }
+
+    pub fn path(&self, index: usize) -> &'a str {
+        std::str::from_utf8(self.record(index).path).expect("Archive paths are checked on open")
+    }
+
+    pub fn index_of(&self, path: &str) -> Option<usize> {
+        self.layout.path_indices.get(path).copied()
+    }
+
+    pub fn moves(&self, index: usize) -> PackedPlacements<'a> {
+        PackedPlacements::new(self.record(index).moves)
+    }
+
+    pub fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>> {
+        unpack_captures(self.record(index).captures)
+    }
+
+    pub fn game(&self, index: usize) -> Game {
+        let record = self.record(index);
+        self.info(index).into_game(
+            unpack_placements(record.moves).0,
+            unpack_captures(record.captures),
+        )
+    }
+
+    /// Deserializes the metadata of the game at `index`
+    pub fn info(&self, index: usize) -> GameInfo {
+        GameInfo::deserialize(&mut Deserializer::new(self.record(index).info))
+            .expect("Failed to deserialize game metadata")
+    }
+
+    /// Reads the summary of the game at `index` without deserializing its metadata
+    pub fn summary(&self, index: usize) -> GameSummary {
+        read_summary(self.record(index).summary)
+    }
+}
+
+impl GameStore for Archive<'_> {
+    fn len(&self) -> usize {
+        Archive::len(self)
+    }
+
+    fn path(&self, index: usize) -> &str {
+        Archive::path(self, index)
+    }
+
+    fn index_of(&self, path: &str) -> Option<usize> {
+        Archive::index_of(self, path)
+    }
+
+    fn moves(&self, index: usize) -> PackedPlacements<'_> {
+        Archive::moves(self, index)
+    }
+
+    fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>> {
+        Archive::captures(self, index)
+    }
+
+    fn info(&self, index: usize) -> Cow<'_, GameInfo> {
+        Cow::Owned(Archive::info(self, index))
+    }
+
+    fn summary(&self, index: usize) -> GameSummary {
+        Archive::summary(self, index)
+    }
+
+    fn game(&self, index: usize) -> Game {
+        Archive::game(self, index)
+    }
+}
+
+/// Writes games into the archive layout
+///
+/// # Arguments
+/// * `metadata` - Metadata of the pack the games were read from
+/// * `games` - The games keyed by their path
+pub fn write_archive(metadata: &PackMetadata, games: &IndexMap<String, CompactGame>) -> Vec<u8> {
+    let metadata = PackMetadata {
+        game_count: games.len(),
+        ..metadata.clone()
+    };
+    let mut metadata_bytes = Vec::new();
+    metadata
+        .serialize(&mut Serializer::new(&mut metadata_bytes))
+        .expect("Failed to serialize pack metadata");
+
+    let mut offsets = Vec::with_capacity((games.len() + 1) * 4);
+    let mut records = Vec::new();
+    for (path, game) in games {
+        offsets.extend((records.len() as u32).to_le_bytes());
+        let path_len = u16::try_from(path.len()).expect("Game path too long");
+        records.extend(path_len.to_le_bytes());
+        records.extend(path.as_bytes());
+        write_summary(&GameSummary::from_info(&game.info), &mut records);
+        records.extend((game.moves.len() as u32).to_le_bytes());
+        records.extend(&game.moves);
+        records.extend((game.captures.len() as u32).to_le_bytes());
+        records.extend(&game.captures);
+        game.info
+            .serialize(&mut Serializer::new(&mut records))
+            .expect("Failed to serialize game metadata");
+    }
+    let records_len = u32::try_from(records.len()).expect("Archive too large");
+    offsets.extend(records_len.to_le_bytes());
+
+    let mut body = metadata_bytes.clone();
+    body.extend(offsets);
+    body.extend(records);
+
+    let mut buf = Vec::with_capacity(ARCHIVE_HEADER_LEN + body.len());
+    buf.extend(ARCHIVE_MAGIC);
+    buf.extend(ARCHIVE_VERSION.to_le_bytes());
+    buf.extend(0u16.to_le_bytes());
+    buf.extend((games.len() as u32).to_le_bytes());
+    buf.extend((metadata_bytes.len() as u32).to_le_bytes());
+    buf.extend(crc32fast::hash(&body).to_le_bytes());
+    buf.extend(body);
+    buf
+}
+
+/// Converts `pack_games` output of any version into the archive layout
+pub fn pack_to_archive(packed: &[u8]) -> Result<Vec<u8>, PackError> {
+    let (metadata, games) = read_pack(packed)?;
+    Ok(write_archive(&metadata, &games))
+}
+
+#[cfg(feature = "mmap")]
+pub use mapped::MappedArchive;
+
+#[cfg(feature = "mmap")]
+mod mapped {
+    use memmap2::Mmap;
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::io;
+    use std::path::Path;
+
+    use super::{Archive, Layout, read_layout};
+    use crate::baduk::{Game, GameInfo, PackMetadata, PackedPlacements, Placement};
+    use crate::store::{GameStore, GameSummary};
+
+    /// An archive file mapped into memory, games are read from the mapping on demand
+    pub struct MappedArchive {
+        mmap: Mmap,
+        layout: Layout,
+    }
+
+    impl MappedArchive {
+        pub fn open(path: impl AsRef<Path>) -> io::Result<MappedArchive> {
+            let file = File::open(path)?;
+            // the file must not be modified while it's mapped, archives are only ever replaced
+            let mmap = unsafe { Mmap::map(&file)? };
+            let layout =
+                read_layout(&mmap).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Ok(MappedArchive { mmap, layout })
+        }
+
+        pub fn archive(&self) -> Archive<'_> {
+            Archive::from_layout(&self.mmap, Cow::Borrowed(&self.layout))
+        }
+
+        pub fn metadata(&self) -> &PackMetadata {
+            &self.layout.metadata
+        }
+    }
+
+    impl GameStore for MappedArchive {
+        fn len(&self) -> usize {
+            self.layout.game_count
+        }
+
+        fn path(&self, index: usize) -> &str {
+            self.archive().path(index)
+        }
+
+        fn index_of(&self, path: &str) -> Option<usize> {
+            self.layout.path_indices.get(path).copied()
+        }
+
+        fn moves(&self, index: usize) -> PackedPlacements<'_> {
+            self.archive().moves(index)
+        }
+
+        fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>> {
+            self.archive().captures(index)
+        }
+
+        fn info(&self, index: usize) -> Cow<'_, GameInfo> {
+            Cow::Owned(self.archive().info(index))
+        }
+
+        fn summary(&self, index: usize) -> GameSummary {
+            self.archive().summary(index)
+        }
+
+        fn game(&self, index: usize) -> Game {
+            self.archive().game(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baduk::{
+        BOARD_SIZE, Color, GameResult, PackOptions, Player, Point, pack_games, pack_games_v1,
+    };
+    use crate::test_utils::test_games;
+
+    #[test]
+    fn test_archive() {
+        let games = test_games();
+        let packed = pack_games(&games, &PackOptions::default());
+        let bytes = pack_to_archive(&packed).unwrap();
+        let archive = Archive::new(&bytes).unwrap();
+
+        assert_eq!(archive.len(), games.len());
+        assert_eq!(archive.metadata().game_count, games.len());
+        for (index, (path, game)) in games.iter().enumerate() {
+            assert_eq!(archive.path(index), path);
+            assert_eq!(archive.index_of(path), Some(index));
+            assert_eq!(archive.moves(index).to_vec(), game.moves);
+            assert_eq!(archive.captures(index), game.captures);
+            assert_eq!(&archive.game(index), game);
+            assert_eq!(archive.info(index), GameInfo::from_game(game));
+            assert_eq!(
+                archive.summary(index),
+                GameSummary::from_info(&GameInfo::from_game(game))
+            );
+        }
+        assert_eq!(archive.index_of("missing.sgf"), None);
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            Archive::new(&corrupted),
+            Err(PackError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            Archive::new(&bytes[..ARCHIVE_HEADER_LEN - 1]),
+            Err(PackError::Truncated)
+        ));
+
+        // a record whose captures are cut short, with a matching checksum
+        let (metadata, mut compact) = read_pack(&packed).unwrap();
+        compact[0].captures.pop();
+        assert!(matches!(
+            Archive::new(&write_archive(&metadata, &compact)),
+            Err(PackError::Truncated)
+        ));
+
+        let mut older = bytes.clone();
+        older[4..6].copy_from_slice(&1u16.to_le_bytes());
+        assert!(matches!(
+            Archive::new(&older),
+            Err(PackError::UnsupportedVersion(1))
+        ));
+    }
+
+    #[test]
+    fn test_archive_summaries() {
+        let mut games = test_games();
+        games[0].player_white = Player::Id(-3, "White".to_string());
+        games[1].result = GameResult::Player(Color::White, None, String::new());
+        games[2].board_size = 13;
+        games[2].setup = vec![Placement {
+            color: Color::Black,
+            point: Point { x: 3, y: 3 },
+        }];
+        let packed = pack_games(&games, &PackOptions::default());
+        let bytes = pack_to_archive(&packed).unwrap();
+        let archive = Archive::new(&bytes).unwrap();
+
+        let summaries = (0..archive.len())
+            .map(|index| archive.summary(index))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summaries[0],
+            GameSummary {
+                board_size: BOARD_SIZE,
+                has_setup: false,
+                has_variations: false,
+                player_black: Some(0),
+                player_white: Some(-3),
+                winner: None,
+            }
+        );
+        assert_eq!(summaries[1].player_white, None);
+        assert_eq!(summaries[1].winner, Some(Color::White));
+        assert_eq!(summaries[2].board_size, 13);
+        assert!(summaries[2].has_setup);
+    }
+
+    #[test]
+    fn test_archive_from_older_pack() {
+        let games = test_games();
+        let bytes = pack_to_archive(&pack_games_v1(&games, "older")).unwrap();
+        let archive = Archive::new(&bytes).unwrap();
+
+        assert_eq!(archive.metadata().version, 1);
+        assert_eq!(archive.metadata().source, "older");
+        for (index, game) in games.values().enumerate() {
+            assert_eq!(&archive.game(index), game);
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use bit_vec::BitVec;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
 use indexmap::IndexMap;
 use rmp_serde::{Deserializer, Serializer};
 use serde::{Deserialize, Serialize};
@@ -6,10 +9,24 @@ use serde_bytes;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
+use std::io::Read;
 
+/// The standard board size, and the largest one supported
+///
+/// Games on smaller boards keep their size in `Game::board_size`, while packed placements
+/// always use this as the stride of their points so the encoding doesn't depend on the size.
 pub const BOARD_SIZE: u8 = 19;
 
+fn default_board_size() -> u8 {
+    BOARD_SIZE
+}
+
+fn default_first_color() -> Color {
+    Color::Black
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub enum SgfDate {
     YearMonthDay(u16, u8, u8),
     YearMonth(u16, u8),
@@ -79,6 +96,7 @@ pub fn parse_sgf_date(date_str: &str) -> SgfDate {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub enum Score {
     Resignation,
     Timeout,
@@ -87,6 +105,7 @@ pub enum Score {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub enum GameResult {
     Player(Color, Option<Score>, String),
     Draw,
@@ -95,18 +114,33 @@ pub enum GameResult {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Ord, PartialOrd)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub enum Color {
     Black,
     White,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Ord, PartialOrd)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub struct Point {
     pub x: u8,
     pub y: u8,
 }
 
+impl Point {
+    /// The point of a pass, `tt` in SGF, outside the board so it is never matched or captured
+    pub const PASS: Point = Point {
+        x: BOARD_SIZE,
+        y: BOARD_SIZE,
+    };
+
+    pub fn is_pass(&self) -> bool {
+        *self == Point::PASS
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Ord, PartialOrd)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub struct Placement {
     pub color: Color,
     pub point: Point,
@@ -119,42 +153,38 @@ pub enum Rotation {
     Degrees270,
 }
 
-pub fn get_rotated(position: &[Placement], rotation: &Rotation) -> Vec<Placement> {
+fn get_rotated_point(point: &Point, rotation: &Rotation, board_size: u8) -> Point {
+    if point.is_pass() {
+        return *point;
+    }
     match rotation {
-        Rotation::Degrees90 => position
-            .iter()
-            .map(|p| Placement {
-                color: p.color,
-                point: Point {
-                    x: BOARD_SIZE - p.point.y - 1,
-                    y: p.point.x,
-                },
-            })
-            .collect(),
-        Rotation::Degrees180 => position
-            .iter()
-            .map(|p| Placement {
-                color: p.color,
-                point: Point {
-                    x: BOARD_SIZE - p.point.x - 1,
-                    y: BOARD_SIZE - p.point.y - 1,
-                },
-            })
-            .collect(),
-        Rotation::Degrees270 => position
-            .iter()
-            .map(|p| Placement {
-                color: p.color,
-                point: Point {
-                    x: p.point.y,
-                    y: BOARD_SIZE - p.point.x - 1,
-                },
-            })
-            .collect(),
+        Rotation::Degrees90 => Point {
+            x: board_size - point.y - 1,
+            y: point.x,
+        },
+        Rotation::Degrees180 => Point {
+            x: board_size - point.x - 1,
+            y: board_size - point.y - 1,
+        },
+        Rotation::Degrees270 => Point {
+            x: point.y,
+            y: board_size - point.x - 1,
+        },
     }
 }
 
-pub fn all_rotations(position: &[Placement]) -> Vec<Vec<Placement>> {
+/// Rotates the placements of `position` clockwise, passes stay passes
+pub fn get_rotated(position: &[Placement], rotation: &Rotation, board_size: u8) -> Vec<Placement> {
+    position
+        .iter()
+        .map(|p| Placement {
+            color: p.color,
+            point: get_rotated_point(&p.point, rotation, board_size),
+        })
+        .collect()
+}
+
+pub fn all_rotations(position: &[Placement], board_size: u8) -> Vec<Vec<Placement>> {
     let mut result = Vec::new();
     result.push(position.to_vec());
     for rotation in [
@@ -162,31 +192,35 @@ pub fn all_rotations(position: &[Placement]) -> Vec<Vec<Placement>> {
         Rotation::Degrees180,
         Rotation::Degrees270,
     ] {
-        result.push(get_rotated(position, &rotation));
+        result.push(get_rotated(position, &rotation, board_size));
     }
     result
 }
 
-pub fn get_rotations(position: &[Placement]) -> HashMap<Rotation, Vec<Placement>> {
+pub fn get_rotations(position: &[Placement], board_size: u8) -> HashMap<Rotation, Vec<Placement>> {
     let mut result = HashMap::new();
     for rotation in [
         Rotation::Degrees90,
         Rotation::Degrees180,
         Rotation::Degrees270,
     ] {
-        result.insert(rotation, get_rotated(position, &rotation));
+        result.insert(rotation, get_rotated(position, &rotation, board_size));
     }
     result
 }
 
-pub fn get_mirrored(position: &[Placement]) -> Vec<Placement> {
+pub fn get_mirrored(position: &[Placement], board_size: u8) -> Vec<Placement> {
     position
         .iter()
         .map(|p| Placement {
             color: p.color,
-            point: Point {
-                x: BOARD_SIZE - p.point.x - 1,
-                y: p.point.y,
+            point: if p.point.is_pass() {
+                p.point
+            } else {
+                Point {
+                    x: board_size - p.point.x - 1,
+                    y: p.point.y,
+                }
             },
         })
         .collect()
@@ -206,15 +240,48 @@ pub fn switch_colors(position: &[Placement]) -> Vec<Placement> {
         .collect()
 }
 
-pub fn match_game(position: &[Placement], moves: &[Placement]) -> Option<usize> {
-    let mut last_move_matched: usize = 0;
+/// Returns the index of the last move needed for all placements of `position` to have been
+/// placed, or `None` if any placement was never played
+///
+/// Setup stones are placed before move 1, the inner `None` means the setup stones alone
+/// contain the position.
+pub fn match_game(
+    position: &[Placement],
+    setup: &[Placement],
+    moves: &[Placement],
+) -> Option<Option<usize>> {
+    let mut last_move_matched = None;
     for placement in position {
+        if setup.contains(placement) {
+            continue;
+        }
         let index = moves.iter().position(|&m| m == *placement)?;
-        last_move_matched = std::cmp::max(index, last_move_matched);
+        last_move_matched = last_move_matched.max(Some(index));
     }
     Some(last_move_matched)
 }
 
+/// Returns the index in `moves` where each placement of `position` was first played, in the
+/// same order as `position`, or `None` if any placement was never played.
+///
+/// Placements that are setup stones have no move index.
+pub fn match_game_moves(
+    position: &[Placement],
+    setup: &[Placement],
+    moves: &[Placement],
+) -> Option<Vec<Option<usize>>> {
+    position
+        .iter()
+        .map(|placement| {
+            if setup.contains(placement) {
+                Some(None)
+            } else {
+                moves.iter().position(|m| m == placement).map(Some)
+            }
+        })
+        .collect()
+}
+
 pub fn check_empty(empty: &[Point], moves: &[Placement]) -> bool {
     for placement in moves {
         if empty.contains(&placement.point) {
@@ -224,11 +291,11 @@ pub fn check_empty(empty: &[Point], moves: &[Placement]) -> bool {
     true
 }
 
-pub fn get_surrounding_points(point: &Point, range: u8) -> Vec<Point> {
+pub fn get_surrounding_points(point: &Point, range: u8, board_size: u8) -> Vec<Point> {
     let mut result = Vec::new();
     let px = point.x as i8;
     let py = point.y as i8;
-    let board_size = BOARD_SIZE as i8;
+    let board_size = board_size as i8;
     let r = range as i8;
     for x in (px - r)..=(px + r) {
         for y in (py - r)..=(py + r) {
@@ -243,10 +310,36 @@ pub fn get_surrounding_points(point: &Point, range: u8) -> Vec<Point> {
     result
 }
 
+/// The packed value of a pass, the first one after the points of the largest board
+const PASS_VALUE: u16 = BOARD_SIZE as u16 * BOARD_SIZE as u16;
+
+fn point_to_value(point: &Point) -> u16 {
+    if point.is_pass() {
+        PASS_VALUE
+    } else {
+        point.x as u16 * BOARD_SIZE as u16 + point.y as u16
+    }
+}
+
+fn value_to_point(value: u16) -> Point {
+    if value == PASS_VALUE {
+        Point::PASS
+    } else {
+        Point {
+            x: (value / BOARD_SIZE as u16) as u8,
+            y: (value % BOARD_SIZE as u16) as u8,
+        }
+    }
+}
+
+/// Packs placements with 9 bits per point and 1 bit per color
+///
+/// Points are numbered `x * BOARD_SIZE + y` on boards of any size, so the packed moves don't
+/// depend on the board they were played on. Passes take the number after the last point.
 pub fn pack_placements(placements: &[Placement]) -> Vec<u8> {
     let points: Vec<u16> = placements
         .iter()
-        .map(|p| p.point.x as u16 * BOARD_SIZE as u16 + p.point.y as u16)
+        .map(|p| point_to_value(&p.point))
         .collect();
 
     let mut point_bits = BitVec::new();
@@ -302,6 +395,32 @@ pub fn pack_captures(captures: &HashMap<usize, Vec<Placement>>) -> Vec<u8> {
     packed
 }
 
+/// The number of bytes taken by the placements packed at the start of `packed`, or `None` if
+/// `packed` is too short to hold them all
+pub fn packed_placements_len(packed: &[u8]) -> Option<usize> {
+    let len = match packed {
+        [high, low, ..] => u16::from_be_bytes([*high, *low]) as usize,
+        _ => return None,
+    };
+    let total_bytes = 2 + (len * 9).div_ceil(8) + len.div_ceil(8);
+    (packed.len() >= total_bytes).then_some(total_bytes)
+}
+
+/// Same as `packed_placements_len` for captures packed by `pack_captures`
+pub fn packed_captures_len(packed: &[u8]) -> Option<usize> {
+    let len = match packed {
+        [high, low, ..] => u16::from_be_bytes([*high, *low]) as usize,
+        _ => return None,
+    };
+    let mut offset = 2;
+    for _ in 0..len {
+        // skip the move number
+        offset += 2;
+        offset += packed_placements_len(packed.get(offset..)?)?;
+    }
+    Some(offset)
+}
+
 pub fn unpack_placements(packed: &[u8]) -> (Vec<Placement>, usize) {
     let len = ((packed[0] as u16) << 8) | (packed[1] as u16);
     let point_bytes_start = 2;
@@ -322,8 +441,6 @@ pub fn unpack_placements(packed: &[u8]) -> (Vec<Placement>, usize) {
             }
         }
 
-        let x = (point_value / BOARD_SIZE as u16) as u8;
-        let y = (point_value % BOARD_SIZE as u16) as u8;
         let color = if color_bits[i] {
             Color::Black
         } else {
@@ -331,7 +448,7 @@ pub fn unpack_placements(packed: &[u8]) -> (Vec<Placement>, usize) {
         };
 
         placements.push(Placement {
-            point: Point { x, y },
+            point: value_to_point(point_value),
             color,
         });
     }
@@ -339,6 +456,112 @@ pub fn unpack_placements(packed: &[u8]) -> (Vec<Placement>, usize) {
     (placements, total_bytes)
 }
 
+/// A view of placements in the encoding of `pack_placements` that can be read and matched
+/// against without unpacking them first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackedPlacements<'a> {
+    packed: &'a [u8],
+    len: usize,
+}
+
+impl<'a> PackedPlacements<'a> {
+    pub fn new(packed: &'a [u8]) -> PackedPlacements<'a> {
+        let len = ((packed[0] as usize) << 8) | (packed[1] as usize);
+        PackedPlacements { packed, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The placements in the encoding of `pack_placements`
+    pub fn packed(&self) -> &'a [u8] {
+        self.packed
+    }
+
+    fn point_value(&self, index: usize) -> u16 {
+        // the 9 bits of a point always span two bytes, most significant bit first
+        let bit = index * 9;
+        let byte = 2 + bit / 8;
+        let two_bytes = ((self.packed[byte] as u16) << 8) | (self.packed[byte + 1] as u16);
+        (two_bytes >> (7 - bit % 8)) & 0x1FF
+    }
+
+    fn is_black(&self, index: usize) -> bool {
+        let color_bytes_start = 2 + (self.len * 9).div_ceil(8);
+        self.packed[color_bytes_start + index / 8] & (1 << (7 - index % 8)) != 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<Placement> {
+        if index >= self.len {
+            return None;
+        }
+        let point_value = self.point_value(index);
+        Some(Placement {
+            color: if self.is_black(index) {
+                Color::Black
+            } else {
+                Color::White
+            },
+            point: value_to_point(point_value),
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Placement> + '_ {
+        (0..self.len).filter_map(|i| self.get(i))
+    }
+
+    /// Index of the first occurrence of `placement`, compared in its packed form
+    pub fn position(&self, placement: &Placement) -> Option<usize> {
+        let point_value = point_to_value(&placement.point);
+        let is_black = placement.color == Color::Black;
+        (0..self.len).find(|&i| self.point_value(i) == point_value && self.is_black(i) == is_black)
+    }
+
+    pub fn to_vec(&self) -> Vec<Placement> {
+        self.iter().collect()
+    }
+}
+
+/// Same as `match_game` but for moves that are still packed
+pub fn match_packed_game(
+    position: &[Placement],
+    setup: &[Placement],
+    moves: &PackedPlacements,
+) -> Option<Option<usize>> {
+    let mut last_move_matched = None;
+    for placement in position {
+        if setup.contains(placement) {
+            continue;
+        }
+        let index = moves.position(placement)?;
+        last_move_matched = last_move_matched.max(Some(index));
+    }
+    Some(last_move_matched)
+}
+
+/// Same as `match_game_moves` but for moves that are still packed
+pub fn match_packed_game_moves(
+    position: &[Placement],
+    setup: &[Placement],
+    moves: &PackedPlacements,
+) -> Option<Vec<Option<usize>>> {
+    position
+        .iter()
+        .map(|placement| {
+            if setup.contains(placement) {
+                Some(None)
+            } else {
+                moves.position(placement).map(Some)
+            }
+        })
+        .collect()
+}
+
 pub fn unpack_captures(packed: &[u8]) -> HashMap<usize, Vec<Placement>> {
     let mut captures = HashMap::new();
     let len = ((packed[0] as u16) << 8) | (packed[1] as u16);
@@ -359,6 +582,7 @@ pub fn unpack_captures(packed: &[u8]) -> HashMap<usize, Vec<Placement>> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub enum Rank {
     Kyu(u8),
     Dan(u8),
@@ -378,6 +602,7 @@ impl fmt::Display for Rank {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
 pub enum Rules {
     Chinese,
     Japanese,
@@ -524,14 +749,621 @@ pub fn parse_sgf_result(result_str: &str) -> GameResult {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum Player {
-    Id(i16, String),
-    Unknown(String),
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub enum Player {
+    Id(i16, String),
+    Unknown(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    pub event: String,
+    pub round: String,
+    pub location: String,
+    pub date: Option<SgfDate>,
+    pub player_black: Player,
+    pub player_white: Player,
+    pub rank_black: Rank,
+    pub rank_white: Rank,
+    pub komi: Option<f32>,
+    pub rules: Option<Rules>,
+    pub result: GameResult,
+    pub board_size: u8,
+    pub handicap: u8,          // from `HA`, 0 for even games
+    pub first_color: Color,    // the player to move first, white in most handicap games
+    pub setup: Vec<Placement>, // stones on the board before move 1, like handicap stones
+    pub moves: Vec<Placement>,
+    pub captures: HashMap<usize, Vec<Placement>>,
+    pub tree: Option<GameTree>, // variations and comments, only kept when asked for
+}
+
+/// The variations and comments of a game record besides the moves that were played
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameTree {
+    pub root_comment: Option<String>, // comment on the game before its first move
+    pub comments: HashMap<usize, String>, // comments on the moves of the game by move index
+    pub setup: HashMap<usize, Vec<Placement>>, // stones added during the game, by the number of moves played before them
+    pub variations: Vec<Variation>,
+}
+
+/// A line of play branching off the game or off another variation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Variation {
+    pub parent: Option<usize>, // index of the variation it branches off, `None` for the game
+    pub start: usize,          // number of moves from the start of the game played before it
+    pub moves: Vec<Placement>,
+    pub comments: HashMap<usize, String>, // by index in `moves`
+}
+
+impl GameTree {
+    /// All moves of the variation at `index` from the start of the game, whose moves are `moves`
+    pub fn line(&self, index: usize, moves: &[Placement]) -> Vec<Placement> {
+        let variation = &self.variations[index];
+        let mut line = match variation.parent {
+            Some(parent) => self.line(parent, moves),
+            None => moves.to_vec(),
+        };
+        line.truncate(variation.start);
+        line.extend(&variation.moves);
+        line
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackedGame {
+    name: String,
+    event: String,
+    round: String,
+    location: String,
+    date: Option<SgfDate>,
+    player_black: Player,
+    player_white: Player,
+    rank_black: Rank,
+    rank_white: Rank,
+    komi: Option<f32>,
+    rules: Option<Rules>,
+    result: GameResult,
+    #[serde(with = "serde_bytes")]
+    moves: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    captures: Vec<u8>,
+}
+
+/// Magic bytes at the start of a versioned pack, packs without them are read as version 0
+const PACK_MAGIC: &[u8; 4] = b"CGPS";
+/// Version written by `pack_games`
+///
+/// * 0 - a bare MessagePack array of games, without a header
+/// * 1 - header with magic bytes, version and checksum, followed by metadata and games
+/// * 2 - like 1, with repeated strings and players moved into tables, optional captures and a
+///   deflate compressed body
+/// * 3 - like 2, with the board size, handicap, first color and setup stones of each game,
+///   passes among the moves, and the variations and comments of games that kept them
+///
+/// Games of earlier versions are read as even 19x19 games without setup stones or passes.
+pub const PACK_VERSION: u16 = 3;
+const PACK_HEADER_LEN: usize = PACK_MAGIC.len() + 2 + 4;
+
+/// Information about a games database stored in its pack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct PackMetadata {
+    pub version: u16,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub created_at: u64, // seconds since the unix epoch, 0 if unknown
+    pub game_count: usize,
+    pub source: String, // description of where the games were collected from
+}
+
+/// Options for writing a pack with `pack_games`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackOptions {
+    pub source: String,         // description of where the games were collected from
+    pub include_captures: bool, // when false captures are recomputed by replaying at load time
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        PackOptions {
+            source: String::new(),
+            include_captures: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PackError {
+    Truncated,
+    UnsupportedVersion(u16),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    GameCountMismatch { expected: usize, actual: usize },
+    InvalidReference(u32),
+    NotAnArchive,
+    Decompress(std::io::Error),
+    Decode(rmp_serde::decode::Error),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::Truncated => write!(f, "pack is truncated"),
+            PackError::UnsupportedVersion(v) => {
+                write!(f, "unsupported pack version {v}, latest is {PACK_VERSION}")
+            }
+            PackError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "pack checksum mismatch, expected {expected:08x} got {actual:08x}"
+            ),
+            PackError::GameCountMismatch { expected, actual } => write!(
+                f,
+                "pack game count mismatch, expected {expected} got {actual}"
+            ),
+            PackError::InvalidReference(id) => {
+                write!(f, "pack references missing table entry {id}")
+            }
+            PackError::NotAnArchive => write!(f, "not an archive, packs must be converted first"),
+            PackError::Decompress(e) => write!(f, "failed to decompress pack: {e}"),
+            PackError::Decode(e) => write!(f, "failed to decode pack: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl From<rmp_serde::decode::Error> for PackError {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        PackError::Decode(e)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackV1 {
+    metadata: PackMetadata,
+    games: Vec<PackedGame>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackV2 {
+    metadata: PackMetadata,
+    strings: Vec<String>, // event, round and location strings referenced by index
+    players: Vec<Player>, // players referenced by index
+    has_captures: bool,
+    games: Vec<PackedGameV2>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackedGameV2 {
+    name: String,
+    event: u32,    // index into `PackV2::strings`
+    round: u32,    // index into `PackV2::strings`
+    location: u32, // index into `PackV2::strings`
+    date: Option<SgfDate>,
+    player_black: u32, // index into `PackV2::players`
+    player_white: u32, // index into `PackV2::players`
+    rank_black: Rank,
+    rank_white: Rank,
+    komi: Option<f32>,
+    rules: Option<Rules>,
+    result: GameResult,
+    #[serde(with = "serde_bytes")]
+    moves: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    captures: Vec<u8>, // empty when the pack has no captures
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackV3 {
+    metadata: PackMetadata,
+    strings: Vec<String>, // event, round and location strings referenced by index
+    players: Vec<Player>, // players referenced by index
+    has_captures: bool,
+    games: Vec<PackedGameV3>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackedGameV3 {
+    name: String,
+    event: u32,    // index into `PackV3::strings`
+    round: u32,    // index into `PackV3::strings`
+    location: u32, // index into `PackV3::strings`
+    date: Option<SgfDate>,
+    player_black: u32, // index into `PackV3::players`
+    player_white: u32, // index into `PackV3::players`
+    rank_black: Rank,
+    rank_white: Rank,
+    komi: Option<f32>,
+    rules: Option<Rules>,
+    result: GameResult,
+    #[serde(with = "serde_bytes")]
+    moves: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    captures: Vec<u8>, // empty when the pack has no captures
+    board_size: u8,
+    handicap: u8,
+    first_color: Color,
+    #[serde(with = "serde_bytes")]
+    setup: Vec<u8>,
+    tree: Option<GameTree>,
+}
+
+/// Assigns each distinct value an index in insertion order
+struct Interner<T> {
+    values: Vec<T>,
+    indices: HashMap<T, u32>,
+}
+
+impl<T: Clone + Eq + std::hash::Hash> Interner<T> {
+    fn new() -> Self {
+        Interner {
+            values: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    fn intern(&mut self, value: &T) -> u32 {
+        if let Some(&index) = self.indices.get(value) {
+            return index;
+        }
+        let index = self.values.len() as u32;
+        self.values.push(value.clone());
+        self.indices.insert(value.clone(), index);
+        index
+    }
+}
+
+fn lookup<T: Clone>(table: &[T], index: u32) -> Result<T, PackError> {
+    table
+        .get(index as usize)
+        .cloned()
+        .ok_or(PackError::InvalidReference(index))
+}
+
+/// Replays the moves of a game after its setup stones and returns the stones captured by each
+/// move
+pub fn compute_captures(
+    setup: &[Placement],
+    moves: &[Placement],
+    board_size: u8,
+) -> HashMap<usize, Vec<Placement>> {
+    let mut captures = HashMap::new();
+    let mut gb = GoBoard::with_setup(board_size, setup);
+
+    for (i, move_) in moves.iter().enumerate() {
+        let cs = gb.make_move(move_);
+        if !cs.is_empty() {
+            captures.insert(i, cs);
+        }
+    }
+    captures
+}
+
+fn pack_metadata(version: u16, game_count: usize, source: &str) -> PackMetadata {
+    let created_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    PackMetadata {
+        version,
+        created_at,
+        game_count,
+        source: source.to_string(),
+    }
+}
+
+fn write_pack(version: u16, body: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(PACK_HEADER_LEN + body.len());
+    buf.extend(PACK_MAGIC);
+    buf.extend(version.to_be_bytes());
+    buf.extend(crc32fast::hash(body).to_be_bytes());
+    buf.extend(body);
+    buf
+}
+
+/// Packs games with a header and metadata
+///
+/// # Arguments
+/// * `games` - The games keyed by their path
+/// * `options` - Where the games come from and whether captures are stored
+pub fn pack_games(games: &IndexMap<String, Game>, options: &PackOptions) -> Vec<u8> {
+    let mut strings = Interner::new();
+    let mut players = Interner::new();
+    let packed_games: Vec<PackedGameV3> = games
+        .iter()
+        .map(|(name, game)| PackedGameV3 {
+            name: name.clone(),
+            event: strings.intern(&game.event),
+            round: strings.intern(&game.round),
+            location: strings.intern(&game.location),
+            date: game.date.clone(),
+            player_black: players.intern(&game.player_black),
+            player_white: players.intern(&game.player_white),
+            rank_black: game.rank_black.clone(),
+            rank_white: game.rank_white.clone(),
+            komi: game.komi,
+            rules: game.rules.clone(),
+            result: game.result.clone(),
+            moves: pack_placements(&game.moves),
+            captures: if options.include_captures {
+                pack_captures(&game.captures)
+            } else {
+                Vec::new()
+            },
+            board_size: game.board_size,
+            handicap: game.handicap,
+            first_color: game.first_color,
+            setup: pack_placements(&game.setup),
+            tree: game.tree.clone(),
+        })
+        .collect();
+
+    let pack = PackV3 {
+        metadata: pack_metadata(PACK_VERSION, packed_games.len(), &options.source),
+        strings: strings.values,
+        players: players.values,
+        has_captures: options.include_captures,
+        games: packed_games,
+    };
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    pack.serialize(&mut Serializer::new(&mut encoder))
+        .expect("Failed to serialize games");
+    let body = encoder.finish().expect("Failed to compress games");
+
+    write_pack(PACK_VERSION, &body)
+}
+
+/// Packs games in the version 1 format, for readers that predate version 2
+///
+/// Version 1 only holds 19x19 games, so games on other boards are left out, setup stones are
+/// written as the first moves and passes are dropped, the way those readers loaded SGF files.
+/// Variations and comments are dropped as well.
+///
+/// # Arguments
+/// * `games` - The games keyed by their path
+/// * `source` - Description of where the games were collected from
+pub fn pack_games_v1(games: &IndexMap<String, Game>, source: &str) -> Vec<u8> {
+    let packed_games: Vec<PackedGame> = games
+        .iter()
+        .filter(|(_, game)| game.board_size == BOARD_SIZE)
+        .map(|(name, game)| {
+            let moves = game
+                .setup
+                .iter()
+                .chain(&game.moves)
+                .filter(|placement| !placement.point.is_pass())
+                .copied()
+                .collect::<Vec<_>>();
+            // the move numbers of the captures only change with setup stones or passes
+            let has_passes = game.moves.iter().any(|m| m.point.is_pass());
+            let captures = if game.setup.is_empty() && !has_passes {
+                pack_captures(&game.captures)
+            } else {
+                pack_captures(&compute_captures(&[], &moves, BOARD_SIZE))
+            };
+            PackedGame {
+                name: name.clone(),
+                event: game.event.clone(),
+                round: game.round.clone(),
+                location: game.location.clone(),
+                date: game.date.clone(),
+                player_black: game.player_black.clone(),
+                player_white: game.player_white.clone(),
+                rank_black: game.rank_black.clone(),
+                rank_white: game.rank_white.clone(),
+                komi: game.komi,
+                rules: game.rules.clone(),
+                result: game.result.clone(),
+                moves: pack_placements(&moves),
+                captures,
+            }
+        })
+        .collect();
+
+    let pack = PackV1 {
+        metadata: pack_metadata(1, packed_games.len(), source),
+        games: packed_games,
+    };
+
+    let mut body = Vec::new();
+    pack.serialize(&mut Serializer::new(&mut body))
+        .expect("Failed to serialize games");
+
+    write_pack(1, &body)
+}
+
+/// Splits a pack into its version and body, checking the checksum of versioned packs
+fn read_pack_header(packed: &[u8]) -> Result<(u16, &[u8]), PackError> {
+    if !packed.starts_with(PACK_MAGIC) {
+        // version 0 packs are a bare MessagePack array, which can't start with the magic bytes
+        return Ok((0, packed));
+    }
+    if packed.len() < PACK_HEADER_LEN {
+        return Err(PackError::Truncated);
+    }
+    let version = u16::from_be_bytes([packed[4], packed[5]]);
+    let expected = u32::from_be_bytes([packed[6], packed[7], packed[8], packed[9]]);
+    let body = &packed[PACK_HEADER_LEN..];
+    let actual = crc32fast::hash(body);
+    if expected != actual {
+        return Err(PackError::ChecksumMismatch { expected, actual });
+    }
+    Ok((version, body))
+}
+
+fn decompress(body: &[u8]) -> Result<Vec<u8>, PackError> {
+    let mut decompressed = Vec::new();
+    DeflateDecoder::new(body)
+        .read_to_end(&mut decompressed)
+        .map_err(PackError::Decompress)?;
+    Ok(decompressed)
+}
+
+/// Resolves the table references of a version 2 pack, recomputing captures if they were omitted
+fn read_pack_v2(pack: PackV2) -> Result<Vec<PackedGame>, PackError> {
+    pack.games
+        .into_iter()
+        .map(|game| {
+            let captures = if pack.has_captures {
+                game.captures
+            } else {
+                let (moves, _) = unpack_placements(&game.moves);
+                pack_captures(&compute_captures(&[], &moves, BOARD_SIZE))
+            };
+            Ok(PackedGame {
+                name: game.name,
+                event: lookup(&pack.strings, game.event)?,
+                round: lookup(&pack.strings, game.round)?,
+                location: lookup(&pack.strings, game.location)?,
+                date: game.date,
+                player_black: lookup(&pack.players, game.player_black)?,
+                player_white: lookup(&pack.players, game.player_white)?,
+                rank_black: game.rank_black,
+                rank_white: game.rank_white,
+                komi: game.komi,
+                rules: game.rules,
+                result: game.result,
+                moves: game.moves,
+                captures,
+            })
+        })
+        .collect()
+}
+
+/// Migrates a game of a version 0 to 2 pack, which only held even 19x19 games
+fn migrate_packed_game(packed: PackedGame) -> (String, CompactGame) {
+    let info = GameInfo {
+        event: packed.event,
+        round: packed.round,
+        location: packed.location,
+        date: packed.date,
+        player_black: packed.player_black,
+        player_white: packed.player_white,
+        rank_black: packed.rank_black,
+        rank_white: packed.rank_white,
+        komi: packed.komi,
+        rules: packed.rules,
+        result: packed.result,
+        board_size: BOARD_SIZE,
+        handicap: 0,
+        first_color: Color::Black,
+        setup: Vec::new(),
+        tree: None,
+    };
+    let game = CompactGame {
+        info,
+        moves: packed.moves,
+        captures: packed.captures,
+    };
+    (packed.name, game)
+}
+
+/// Resolves the table references of a version 3 pack, recomputing captures if they were omitted
+fn read_pack_v3(pack: PackV3) -> Result<Vec<(String, CompactGame)>, PackError> {
+    pack.games
+        .into_iter()
+        .map(|game| {
+            let setup = unpack_setup(&game.setup);
+            let captures = if pack.has_captures {
+                game.captures
+            } else {
+                let (moves, _) = unpack_placements(&game.moves);
+                pack_captures(&compute_captures(&setup, &moves, game.board_size))
+            };
+            let info = GameInfo {
+                event: lookup(&pack.strings, game.event)?,
+                round: lookup(&pack.strings, game.round)?,
+                location: lookup(&pack.strings, game.location)?,
+                date: game.date,
+                player_black: lookup(&pack.players, game.player_black)?,
+                player_white: lookup(&pack.players, game.player_white)?,
+                rank_black: game.rank_black,
+                rank_white: game.rank_white,
+                komi: game.komi,
+                rules: game.rules,
+                result: game.result,
+                board_size: game.board_size,
+                handicap: game.handicap,
+                first_color: game.first_color,
+                setup,
+                tree: game.tree,
+            };
+            let compact = CompactGame {
+                info,
+                moves: game.moves,
+                captures,
+            };
+            Ok((game.name, compact))
+        })
+        .collect()
+}
+
+fn unpack_setup(packed: &[u8]) -> Vec<Placement> {
+    if packed.is_empty() {
+        Vec::new()
+    } else {
+        unpack_placements(packed).0
+    }
+}
+
+/// Reads the metadata and games of a pack, migrating older pack versions
+pub fn read_pack(
+    packed: &[u8],
+) -> Result<(PackMetadata, IndexMap<String, CompactGame>), PackError> {
+    let (version, body) = read_pack_header(packed)?;
+    let (metadata, games) = match version {
+        0 => {
+            let packed_games = Vec::<PackedGame>::deserialize(&mut Deserializer::new(body))?;
+            let metadata = PackMetadata {
+                version,
+                created_at: 0,
+                game_count: packed_games.len(),
+                source: String::new(),
+            };
+            let games = packed_games.into_iter().map(migrate_packed_game);
+            (metadata, games.collect::<Vec<_>>())
+        }
+        1 => {
+            let pack = PackV1::deserialize(&mut Deserializer::new(body))?;
+            let games = pack.games.into_iter().map(migrate_packed_game);
+            (pack.metadata, games.collect())
+        }
+        2 => {
+            let decompressed = decompress(body)?;
+            let pack = PackV2::deserialize(&mut Deserializer::new(&decompressed[..]))?;
+            let metadata = pack.metadata.clone();
+            let games = read_pack_v2(pack)?.into_iter().map(migrate_packed_game);
+            (metadata, games.collect())
+        }
+        3 => {
+            let decompressed = decompress(body)?;
+            let pack = PackV3::deserialize(&mut Deserializer::new(&decompressed[..]))?;
+            let metadata = pack.metadata.clone();
+            (metadata, read_pack_v3(pack)?)
+        }
+        _ => return Err(PackError::UnsupportedVersion(version)),
+    };
+
+    if metadata.game_count != games.len() {
+        return Err(PackError::GameCountMismatch {
+            expected: metadata.game_count,
+            actual: games.len(),
+        });
+    }
+
+    Ok((metadata, games.into_iter().collect()))
+}
+
+pub fn unpack_games(packed: &[u8]) -> Result<IndexMap<String, Game>, PackError> {
+    Ok(unpack_compact_games(packed)?
+        .into_iter()
+        .map(|(name, game)| (name, game.to_game()))
+        .collect::<IndexMap<String, Game>>())
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Game {
+/// Everything about a game except its moves and captures
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameInfo {
     pub event: String,
     pub round: String,
     pub location: String,
@@ -543,35 +1375,21 @@ pub struct Game {
     pub komi: Option<f32>,
     pub rules: Option<Rules>,
     pub result: GameResult,
-    pub moves: Vec<Placement>,
-    pub captures: HashMap<usize, Vec<Placement>>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct PackedGame {
-    name: String,
-    event: String,
-    round: String,
-    location: String,
-    date: Option<SgfDate>,
-    player_black: Player,
-    player_white: Player,
-    rank_black: Rank,
-    rank_white: Rank,
-    komi: Option<f32>,
-    rules: Option<Rules>,
-    result: GameResult,
-    #[serde(with = "serde_bytes")]
-    moves: Vec<u8>,
-    #[serde(with = "serde_bytes")]
-    captures: Vec<u8>,
+    #[serde(default = "default_board_size")]
+    pub board_size: u8, // missing from SQLite stores written before games had a board size
+    #[serde(default)]
+    pub handicap: u8,
+    #[serde(default = "default_first_color")]
+    pub first_color: Color,
+    #[serde(default)]
+    pub setup: Vec<Placement>,
+    #[serde(default)]
+    pub tree: Option<GameTree>,
 }
 
-pub fn pack_games(games: &IndexMap<String, Game>) -> Vec<u8> {
-    let packed_games: Vec<PackedGame> = games
-        .iter()
-        .map(|(name, game)| PackedGame {
-            name: name.clone(),
+impl GameInfo {
+    pub fn from_game(game: &Game) -> GameInfo {
+        GameInfo {
             event: game.event.clone(),
             round: game.round.clone(),
             location: game.location.clone(),
@@ -583,54 +1401,92 @@ pub fn pack_games(games: &IndexMap<String, Game>) -> Vec<u8> {
             komi: game.komi,
             rules: game.rules.clone(),
             result: game.result.clone(),
+            board_size: game.board_size,
+            handicap: game.handicap,
+            first_color: game.first_color,
+            setup: game.setup.clone(),
+            tree: game.tree.clone(),
+        }
+    }
+
+    /// Combines the info with moves and captures into a full `Game`
+    pub fn into_game(
+        self,
+        moves: Vec<Placement>,
+        captures: HashMap<usize, Vec<Placement>>,
+    ) -> Game {
+        Game {
+            event: self.event,
+            round: self.round,
+            location: self.location,
+            date: self.date,
+            player_black: self.player_black,
+            player_white: self.player_white,
+            rank_black: self.rank_black,
+            rank_white: self.rank_white,
+            komi: self.komi,
+            rules: self.rules,
+            result: self.result,
+            board_size: self.board_size,
+            handicap: self.handicap,
+            first_color: self.first_color,
+            setup: self.setup,
+            moves,
+            captures,
+            tree: self.tree,
+        }
+    }
+}
+
+/// A game kept in memory with its moves and captures in their packed encoding
+///
+/// The moves can be matched against directly through `moves()`, the full `Game` is only
+/// decoded with `to_game()` when it's needed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactGame {
+    pub info: GameInfo,
+    pub(crate) moves: Vec<u8>,
+    pub(crate) captures: Vec<u8>,
+}
+
+impl CompactGame {
+    pub fn from_game(game: &Game) -> CompactGame {
+        CompactGame {
+            info: GameInfo::from_game(game),
             moves: pack_placements(&game.moves),
             captures: pack_captures(&game.captures),
-        })
-        .collect();
+        }
+    }
 
-    let mut buf = Vec::new();
-    packed_games
-        .serialize(&mut Serializer::new(&mut buf))
-        .expect("Failed to serialize games");
-    buf
-}
+    pub fn moves(&self) -> PackedPlacements<'_> {
+        PackedPlacements::new(&self.moves)
+    }
 
-pub fn unpack_games(packed: &[u8]) -> IndexMap<String, Game> {
-    let mut deserializer = Deserializer::new(packed);
-    let packed_games: Vec<PackedGame> =
-        Vec::<PackedGame>::deserialize(&mut deserializer).expect("Failed to deserialize games");
+    pub fn captures(&self) -> HashMap<usize, Vec<Placement>> {
+        unpack_captures(&self.captures)
+    }
 
-    packed_games
-        .into_iter()
-        .map(|packed| {
-            (
-                packed.name,
-                Game {
-                    event: packed.event,
-                    round: packed.round,
-                    location: packed.location,
-                    date: packed.date,
-                    player_black: packed.player_black,
-                    player_white: packed.player_white,
-                    rank_black: packed.rank_black,
-                    rank_white: packed.rank_white,
-                    komi: packed.komi,
-                    rules: packed.rules,
-                    result: packed.result,
-                    moves: unpack_placements(&packed.moves).0,
-                    captures: unpack_captures(&packed.captures),
-                },
-            )
-        })
-        .collect::<IndexMap<String, Game>>()
+    pub fn to_game(&self) -> Game {
+        self.info.clone().into_game(
+            unpack_placements(&self.moves).0,
+            unpack_captures(&self.captures),
+        )
+    }
+}
+
+/// Reads games from `pack_games` output, keeping their moves and captures packed
+pub fn unpack_compact_games(packed: &[u8]) -> Result<IndexMap<String, CompactGame>, PackError> {
+    read_pack(packed).map(|(_, games)| games)
 }
 
-pub fn check_within_one_quadrant(position: &[Placement]) -> bool {
+pub fn check_within_one_quadrant(position: &[Placement], board_size: u8) -> bool {
     if position.is_empty() {
         return true;
     }
 
-    let mid = BOARD_SIZE / 2;
+    let mid = board_size / 2;
+    // boards with an even size have no middle lines
+    let has_middle_lines = board_size % 2 == 1;
     let mut quadrant = None;
 
     for placement in position {
@@ -638,7 +1494,7 @@ pub fn check_within_one_quadrant(position: &[Placement]) -> bool {
         let y = placement.point.y;
 
         // Return false if point is on middle lines
-        if x == mid || y == mid {
+        if has_middle_lines && (x == mid || y == mid) {
             return false;
         }
 
@@ -659,7 +1515,7 @@ pub fn check_within_one_quadrant(position: &[Placement]) -> bool {
     true
 }
 
-pub fn get_connected_groups(position: &[Placement]) -> Vec<Vec<Placement>> {
+pub fn get_connected_groups(position: &[Placement], board_size: u8) -> Vec<Vec<Placement>> {
     if position.is_empty() {
         return Vec::new();
     }
@@ -693,7 +1549,7 @@ pub fn get_connected_groups(position: &[Placement]) -> Vec<Vec<Placement>> {
                 let new_y = current.point.y as i8 + dy;
 
                 // Check if the new point is within board bounds
-                if new_x < 0 || new_x >= BOARD_SIZE as i8 || new_y < 0 || new_y >= BOARD_SIZE as i8
+                if new_x < 0 || new_x >= board_size as i8 || new_y < 0 || new_y >= board_size as i8
                 {
                     continue;
                 }
@@ -722,7 +1578,11 @@ pub fn get_connected_groups(position: &[Placement]) -> Vec<Vec<Placement>> {
     groups
 }
 
-pub fn get_group_liberties(group: &[Placement], position: &[Placement]) -> Vec<Point> {
+pub fn get_group_liberties(
+    group: &[Placement],
+    position: &[Placement],
+    board_size: u8,
+) -> Vec<Point> {
     let mut liberties = HashSet::new();
 
     for placement in group {
@@ -739,7 +1599,7 @@ pub fn get_group_liberties(group: &[Placement], position: &[Placement]) -> Vec<P
             let new_y = placement.point.y as i8 + dy;
 
             // Check if the new point is within board bounds
-            if new_x < 0 || new_x >= BOARD_SIZE as i8 || new_y < 0 || new_y >= BOARD_SIZE as i8 {
+            if new_x < 0 || new_x >= board_size as i8 || new_y < 0 || new_y >= board_size as i8 {
                 continue;
             }
 
@@ -758,16 +1618,16 @@ pub fn get_group_liberties(group: &[Placement], position: &[Placement]) -> Vec<P
     liberties.into_iter().collect()
 }
 
-pub fn get_captured_groups(position: &[Placement]) -> Vec<Vec<Placement>> {
-    let groups = get_connected_groups(position);
+pub fn get_captured_groups(position: &[Placement], board_size: u8) -> Vec<Vec<Placement>> {
+    let groups = get_connected_groups(position, board_size);
     groups
         .into_iter()
-        .filter(|group| get_group_liberties(group, position).is_empty())
+        .filter(|group| get_group_liberties(group, position, board_size).is_empty())
         .collect()
 }
 
-pub fn get_captured_stones(position: &[Placement]) -> Vec<Placement> {
-    get_captured_groups(position)
+pub fn get_captured_stones(position: &[Placement], board_size: u8) -> Vec<Placement> {
+    get_captured_groups(position, board_size)
         .into_iter()
         .flatten()
         .collect()
@@ -779,13 +1639,16 @@ pub struct GameState {
     pub number_of_moves: usize,
 }
 
-pub fn calculate_position(moves: &[Placement]) -> GameState {
+pub fn calculate_position(moves: &[Placement], board_size: u8) -> GameState {
     let mut position = Vec::new();
     let mut captures = Vec::new();
 
     for &placement in moves {
+        if placement.point.is_pass() {
+            continue;
+        }
         position.push(placement);
-        let captured = get_captured_stones(&position);
+        let captured = get_captured_stones(&position, board_size);
         captures.extend(captured.clone());
         position.retain(|p| !captured.contains(p));
     }
@@ -811,14 +1674,14 @@ pub enum Neighbor {
     Edge,
 }
 
-pub fn get_neighbors(point: &Point, position: &[Placement]) -> [Neighbor; 4] {
+pub fn get_neighbors(point: &Point, position: &[Placement], board_size: u8) -> [Neighbor; 4] {
     let mut result = [Neighbor::Edge; 4];
     for (i, direction) in DIRECTIONS.iter().enumerate() {
         let (dx, dy) = *direction;
         let new_x = point.x as i8 + dx;
         let new_y = point.y as i8 + dy;
 
-        if new_x < 0 || new_x >= BOARD_SIZE as i8 || new_y < 0 || new_y >= BOARD_SIZE as i8 {
+        if new_x < 0 || new_x >= board_size as i8 || new_y < 0 || new_y >= board_size as i8 {
             continue;
         }
 
@@ -838,6 +1701,7 @@ pub fn get_neighbors(point: &Point, position: &[Placement]) -> [Neighbor; 4] {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GoBoard {
+    pub board_size: u8,
     pub position: Vec<Placement>,
     pub captures: Vec<Vec<Placement>>,
     pub groups: Vec<Vec<Placement>>,
@@ -845,19 +1709,31 @@ pub struct GoBoard {
 
 impl Default for GoBoard {
     fn default() -> Self {
-        Self::new()
+        Self::new(BOARD_SIZE)
     }
 }
 
 impl GoBoard {
-    pub fn new() -> GoBoard {
+    pub fn new(board_size: u8) -> GoBoard {
         GoBoard {
+            board_size,
             position: Vec::new(),
             captures: Vec::new(),
             groups: Vec::new(),
         }
     }
 
+    /// A board with the setup stones of a game placed, ready for its first move
+    pub fn with_setup(board_size: u8, setup: &[Placement]) -> GoBoard {
+        let mut board = GoBoard::new(board_size);
+        for placement in setup {
+            board.make_move(placement);
+        }
+        // captures are only recorded for moves
+        board.captures.clear();
+        board
+    }
+
     fn capture_group(&mut self, group: &[Placement]) {
         self.groups.retain(|g| g != group);
         self.captures.push(group.to_vec());
@@ -884,9 +1760,9 @@ impl GoBoard {
                 let neighbor_y = placement.point.y as i8 + dy;
 
                 if neighbor_x < 0
-                    || neighbor_x >= BOARD_SIZE as i8
+                    || neighbor_x >= self.board_size as i8
                     || neighbor_y < 0
-                    || neighbor_y >= BOARD_SIZE as i8
+                    || neighbor_y >= self.board_size as i8
                 {
                     continue;
                 }
@@ -906,6 +1782,9 @@ impl GoBoard {
     }
 
     pub fn make_move(&mut self, move_: &Placement) -> Vec<Placement> {
+        if move_.point.is_pass() {
+            return Vec::new();
+        }
         if self.position.iter().any(|p| p.point == move_.point) {
             // bad sgf with duplicate move, we ignore it for now
             return Vec::new();
@@ -913,7 +1792,7 @@ impl GoBoard {
         self.position.push(*move_);
         let mut same_color = Vec::new();
         let mut other_color = Vec::new();
-        for neighbor in get_neighbors(&move_.point, &self.position) {
+        for neighbor in get_neighbors(&move_.point, &self.position, self.board_size) {
             if let Neighbor::Stone(Placement { color, point }) = neighbor {
                 if color == move_.color {
                     same_color.push(point);
@@ -998,7 +1877,7 @@ mod tests {
     #[test]
     fn test_get_surrounding_points_range1() {
         let point = Point { x: 1, y: 1 };
-        let surrounding_points = get_surrounding_points(&point, 1);
+        let surrounding_points = get_surrounding_points(&point, 1, BOARD_SIZE);
         assert_eq!(surrounding_points.len(), 8);
         assert!(surrounding_points.contains(&Point { x: 0, y: 0 }));
         assert!(surrounding_points.contains(&Point { x: 0, y: 1 }));
@@ -1012,7 +1891,7 @@ mod tests {
     #[test]
     fn test_get_surrounding_points_at_corner() {
         let point = Point { x: 0, y: 0 };
-        let surrounding_points = get_surrounding_points(&point, 1);
+        let surrounding_points = get_surrounding_points(&point, 1, BOARD_SIZE);
         assert_eq!(surrounding_points.len(), 3);
         assert!(surrounding_points.contains(&Point { x: 0, y: 1 }));
         assert!(surrounding_points.contains(&Point { x: 1, y: 0 }));
@@ -1025,7 +1904,7 @@ mod tests {
             x: BOARD_SIZE - 1,
             y: BOARD_SIZE - 1,
         };
-        let surrounding_points = get_surrounding_points(&point, 1);
+        let surrounding_points = get_surrounding_points(&point, 1, BOARD_SIZE);
         assert_eq!(surrounding_points.len(), 3);
         assert!(surrounding_points.contains(&Point {
             x: BOARD_SIZE - 2,
@@ -1044,21 +1923,21 @@ mod tests {
     #[test]
     fn test_surrounding_points_range2() {
         let point = Point { x: 3, y: 3 };
-        let surrounding_points = get_surrounding_points(&point, 2);
+        let surrounding_points = get_surrounding_points(&point, 2, BOARD_SIZE);
         assert_eq!(surrounding_points.len(), 24);
     }
 
     #[test]
     fn test_surrounding_points_range2_at_corner() {
         let point = Point { x: 0, y: 0 };
-        let surrounding_points = get_surrounding_points(&point, 2);
+        let surrounding_points = get_surrounding_points(&point, 2, BOARD_SIZE);
         assert_eq!(surrounding_points.len(), 8);
     }
 
     #[test]
     fn test_surrounding_points_range2_1_1_point() {
         let point = Point { x: 1, y: 1 };
-        let surrounding_points = get_surrounding_points(&point, 2);
+        let surrounding_points = get_surrounding_points(&point, 2, BOARD_SIZE);
         assert_eq!(surrounding_points.len(), 15);
     }
 
@@ -1079,7 +1958,7 @@ mod tests {
             },
         ];
 
-        let mirrored = get_mirrored(&position);
+        let mirrored = get_mirrored(&position, BOARD_SIZE);
         assert_eq!(mirrored.len(), 3);
         assert!(mirrored.contains(&Placement {
             color: Color::Black,
@@ -1093,6 +1972,20 @@ mod tests {
             color: Color::Black,
             point: Point { x: 9, y: 9 },
         }));
+
+        let corner = [Placement {
+            color: Color::Black,
+            point: Point { x: 0, y: 2 },
+        }];
+        assert_eq!(get_mirrored(&corner, 9)[0].point, Point { x: 8, y: 2 });
+        assert_eq!(
+            get_rotated(&corner, &Rotation::Degrees90, 9)[0].point,
+            Point { x: 6, y: 0 }
+        );
+        assert_eq!(
+            get_rotated(&corner, &Rotation::Degrees180, 13)[0].point,
+            Point { x: 12, y: 10 }
+        );
     }
 
     proptest! {
@@ -1145,13 +2038,18 @@ mod tests {
                 komi: None,
                 rules: None,
                 result: parse_sgf_result("B+R"),
+                board_size: BOARD_SIZE,
+                handicap: 0,
+                first_color: Color::Black,
+                setup: Vec::new(),
+                tree: None,
                 moves,
                 captures: HashMap::new()
             })
         }).collect::<IndexMap<_, _>>())) {
             let games_indexed: IndexMap<_, _> = games.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-            let packed = pack_games(&games_indexed);
-            let unpacked = unpack_games(&packed);
+            let packed = pack_games(&games_indexed, &PackOptions::default());
+            let unpacked = unpack_games(&packed).unwrap();
             assert_eq!(games, unpacked);
         }
 
@@ -1184,13 +2082,18 @@ mod tests {
                 komi: None,
                 rules: None,
                 result: parse_sgf_result("B+R"),
+                board_size: BOARD_SIZE,
+                handicap: 0,
+                first_color: Color::Black,
+                setup: Vec::new(),
+                tree: None,
                 moves,
                 captures: HashMap::new()
             })
         }).collect::<IndexMap<_, _>>())) {
             let games_indexed: IndexMap<_, _> = games.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-            let packed = pack_games(&games_indexed);
-            let unpacked = unpack_games(&packed);
+            let packed = pack_games(&games_indexed, &PackOptions::default());
+            let unpacked = unpack_games(&packed).unwrap();
             assert_eq!(games, unpacked);
         }
 
@@ -1223,22 +2126,27 @@ mod tests {
                 komi: None,
                 rules: None,
                 result: parse_sgf_result("B+R"),
+                board_size: BOARD_SIZE,
+                handicap: 0,
+                first_color: Color::Black,
+                setup: Vec::new(),
+                tree: None,
                 moves,
                 captures: HashMap::new()
             })
         }).collect::<IndexMap<_, _>>())) {
             let games_indexed: IndexMap<_, _> = games.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-            let packed = pack_games(&games_indexed);
-            let unpacked = unpack_games(&packed);
+            let packed = pack_games(&games_indexed, &PackOptions::default());
+            let unpacked = unpack_games(&packed).unwrap();
             assert_eq!(games, unpacked);
         }
     }
 
     proptest! {
         #[test]
-        fn test_get_mirrored_property(position in prop::collection::vec(
+        fn test_packed_placements(placements in prop::collection::vec(
             (any::<bool>(), any::<u8>(), any::<u8>()),
-            0..100
+            0..500
         ).prop_map(|v| v.into_iter().map(|(is_black, x, y)| {
             Placement {
                 color: if is_black { Color::Black } else { Color::White },
@@ -1248,73 +2156,507 @@ mod tests {
                 }
             }
         }).collect::<Vec<_>>())) {
-            let mirrored = get_mirrored(&position);
-            let double_mirrored = get_mirrored(&mirrored);
-            assert_eq!(position, double_mirrored);
+            let packed = pack_placements(&placements);
+            assert_eq!(packed_placements_len(&packed), Some(packed.len()));
+            assert_eq!(packed_placements_len(&packed[..packed.len() - 1]), None);
+            let packed_placements = PackedPlacements::new(&packed);
+            assert_eq!(packed_placements.len(), placements.len());
+            assert_eq!(packed_placements.to_vec(), placements);
+            for placement in placements.iter().take(20) {
+                assert_eq!(
+                    packed_placements.position(placement),
+                    placements.iter().position(|p| p == placement)
+                );
+            }
+            let position = placements.iter().rev().take(5).cloned().collect::<Vec<_>>();
+            let setup = &placements[..placements.len().min(2)];
+            assert_eq!(
+                match_packed_game(&position, setup, &packed_placements),
+                match_game(&position, setup, &placements)
+            );
+            assert_eq!(
+                match_packed_game(&switch_colors(&position), &[], &packed_placements),
+                match_game(&switch_colors(&position), &[], &placements)
+            );
         }
     }
 
     #[test]
-    fn test_check_within_one_quadrant() {
-        // Empty position
-        assert!(check_within_one_quadrant(&[]));
+    fn test_pack_without_captures() {
+        let placement = |color, x, y| Placement {
+            color,
+            point: Point { x, y },
+        };
+        let moves = vec![
+            placement(Color::White, 0, 0),
+            placement(Color::Black, 1, 0),
+            placement(Color::White, 10, 10),
+            placement(Color::Black, 0, 1),
+        ];
+        let captures = compute_captures(&[], &moves, BOARD_SIZE);
+        assert_eq!(captures, HashMap::from([(3, vec![moves[0]])]));
+
+        let game = |player_black: Player| Game {
+            event: "Shared Event".to_string(),
+            round: String::new(),
+            location: "Shared Place".to_string(),
+            date: None,
+            player_black,
+            player_white: Player::Id(2, "White".to_string()),
+            rank_black: Rank::Custom(String::new()),
+            rank_white: Rank::Custom(String::new()),
+            komi: None,
+            rules: None,
+            result: GameResult::Void,
+            board_size: BOARD_SIZE,
+            handicap: 0,
+            first_color: Color::Black,
+            setup: Vec::new(),
+            tree: None,
+            moves: moves.clone(),
+            captures: captures.clone(),
+        };
+        let mut games = IndexMap::new();
+        games.insert("a".to_string(), game(Player::Id(1, "Black".to_string())));
+        games.insert(
+            "b".to_string(),
+            game(Player::Unknown("Someone".to_string())),
+        );
+
+        let with_captures = pack_games(&games, &PackOptions::default());
+        let without_captures = pack_games(
+            &games,
+            &PackOptions {
+                include_captures: false,
+                ..PackOptions::default()
+            },
+        );
+        assert!(without_captures.len() <= with_captures.len());
+        assert_eq!(unpack_games(&with_captures).unwrap(), games);
+        assert_eq!(unpack_games(&without_captures).unwrap(), games);
 
-        // Single placement in top-left quadrant
-        assert!(check_within_one_quadrant(&[Placement {
-            color: Color::Black,
-            point: Point { x: 5, y: 5 },
-        }]));
+        // captures are recomputed on the board size of each game
+        let moves = vec![
+            placement(Color::White, 8, 8),
+            placement(Color::Black, 7, 8),
+            placement(Color::Black, 8, 7),
+        ];
+        let small_game = Game {
+            board_size: 9,
+            captures: compute_captures(&[], &moves, 9),
+            moves,
+            ..game(Player::Id(1, "Black".to_string()))
+        };
+        assert_eq!(small_game.captures.len(), 1);
+        games.insert("c".to_string(), small_game);
+
+        // and after the setup stones, which only capture together with the moves
+        let setup = vec![placement(Color::Black, 1, 0)];
+        let moves = vec![placement(Color::White, 0, 0), placement(Color::Black, 0, 1)];
+        let handicap_game = Game {
+            handicap: 2,
+            first_color: Color::White,
+            captures: compute_captures(&setup, &moves, BOARD_SIZE),
+            setup,
+            moves,
+            ..game(Player::Id(1, "Black".to_string()))
+        };
+        assert_eq!(
+            handicap_game.captures,
+            HashMap::from([(1, vec![placement(Color::White, 0, 0)])])
+        );
+        games.insert("d".to_string(), handicap_game);
+
+        // variations and comments are kept with the games that have them
+        let tree = GameTree {
+            root_comment: Some("Opening lesson".to_string()),
+            comments: HashMap::from([(1, "Solid".to_string())]),
+            setup: HashMap::from([(2, vec![placement(Color::White, 16, 16)])]),
+            variations: vec![Variation {
+                parent: None,
+                start: 2,
+                moves: vec![placement(Color::Black, 3, 3)],
+                comments: HashMap::from([(0, "Also possible".to_string())]),
+            }],
+        };
+        let commented_game = Game {
+            tree: Some(tree),
+            ..game(Player::Id(1, "Black".to_string()))
+        };
+        games.insert("e".to_string(), commented_game);
 
-        // Multiple placements in top-left quadrant
-        assert!(check_within_one_quadrant(&[
+        // a setup stone and a pass leave as many moves in version 1, but the capture before the
+        // pass moves a number up
+        let mut moves = game(Player::Id(1, "Black".to_string())).moves;
+        moves.extend([
             Placement {
-                color: Color::Black,
-                point: Point { x: 5, y: 5 },
+                color: Color::White,
+                point: Point::PASS,
+            },
+            placement(Color::Black, 15, 15),
+        ]);
+        let setup = vec![placement(Color::Black, 5, 5)];
+        let passing_game = Game {
+            captures: compute_captures(&setup, &moves, BOARD_SIZE),
+            setup,
+            moves,
+            ..game(Player::Id(1, "Black".to_string()))
+        };
+        games.insert("f".to_string(), passing_game);
+        let without_captures = pack_games(
+            &games,
+            &PackOptions {
+                include_captures: false,
+                ..PackOptions::default()
+            },
+        );
+        assert_eq!(unpack_games(&without_captures).unwrap(), games);
+
+        // version 1 holds the games the way its readers loaded them from SGF
+        let games_v1 = unpack_games(&pack_games_v1(&games, "")).unwrap();
+        assert_eq!(
+            games_v1.keys().collect::<Vec<_>>(),
+            ["a", "b", "d", "e", "f"]
+        );
+        assert_eq!(games_v1["a"], games["a"]);
+        let moves = [&games["d"].setup[..], &games["d"].moves[..]].concat();
+        assert_eq!(
+            games_v1["d"],
+            Game {
+                handicap: 0,
+                first_color: Color::Black,
+                setup: Vec::new(),
+                captures: compute_captures(&[], &moves, BOARD_SIZE),
+                moves,
+                ..games["d"].clone()
+            }
+        );
+        assert_eq!(games_v1["e"].tree, None);
+        assert_eq!(games_v1["f"].moves.len(), games["f"].moves.len());
+        assert_eq!(
+            games_v1["f"].captures,
+            HashMap::from([(4, vec![placement(Color::White, 0, 0)])])
+        );
+    }
+
+    #[test]
+    fn test_game_tree_lines() {
+        let placement = |color, x, y| Placement {
+            color,
+            point: Point { x, y },
+        };
+        let moves = vec![
+            placement(Color::Black, 3, 3),
+            placement(Color::White, 15, 15),
+            placement(Color::Black, 3, 15),
+        ];
+        let variation = |parent, start, moves| Variation {
+            parent,
+            start,
+            moves,
+            comments: HashMap::new(),
+        };
+        let tree = GameTree {
+            variations: vec![
+                variation(None, 1, vec![placement(Color::White, 15, 3)]),
+                // branches off the first variation, replacing its only move
+                variation(Some(0), 1, vec![placement(Color::White, 2, 5)]),
+                variation(None, 3, vec![placement(Color::White, 16, 3)]),
+            ],
+            ..GameTree::default()
+        };
+        assert_eq!(
+            tree.line(0, &moves),
+            vec![moves[0], placement(Color::White, 15, 3)]
+        );
+        assert_eq!(
+            tree.line(1, &moves),
+            vec![moves[0], placement(Color::White, 2, 5)]
+        );
+        assert_eq!(tree.line(2, &moves)[..3], moves[..]);
+        assert_eq!(tree.line(2, &moves).len(), 4);
+    }
+
+    #[test]
+    fn test_pack_versions() {
+        let mut games = IndexMap::new();
+        games.insert(
+            "test/game".to_string(),
+            Game {
+                event: "Test Event".to_string(),
+                round: "Test Round".to_string(),
+                location: "Test Place".to_string(),
+                date: Some(SgfDate::YearMonthDay(2024, 1, 1)),
+                player_black: Player::Id(1, "Black".to_string()),
+                player_white: Player::Id(2, "White".to_string()),
+                rank_black: Rank::Pro(9),
+                rank_white: Rank::Pro(9),
+                komi: Some(6.5),
+                rules: Some(Rules::Japanese),
+                result: parse_sgf_result("B+R"),
+                board_size: BOARD_SIZE,
+                handicap: 0,
+                first_color: Color::Black,
+                setup: Vec::new(),
+                tree: None,
+                moves: vec![
+                    Placement {
+                        color: Color::Black,
+                        point: Point { x: 3, y: 3 },
+                    },
+                    Placement {
+                        color: Color::White,
+                        point: Point { x: 15, y: 15 },
+                    },
+                ],
+                captures: HashMap::new(),
+            },
+        );
+
+        let options = PackOptions {
+            source: "test source".to_string(),
+            ..PackOptions::default()
+        };
+        let packed = pack_games(&games, &options);
+        let (metadata, unpacked) = read_pack(&packed).unwrap();
+        assert_eq!(metadata.version, PACK_VERSION);
+        assert_eq!(metadata.game_count, 1);
+        assert_eq!(metadata.source, "test source");
+        assert!(metadata.created_at > 0);
+        assert_eq!(unpacked["test/game"].to_game(), games["test/game"]);
+
+        let packed_v1 = pack_games_v1(&games, "test source");
+        let (metadata, unpacked) = read_pack(&packed_v1).unwrap();
+        assert_eq!(metadata.version, 1);
+        assert_eq!(metadata.source, "test source");
+        assert_eq!(unpacked["test/game"].to_game(), games["test/game"]);
+
+        // version 0 packs are a bare array of games without a header
+        let pack =
+            PackV1::deserialize(&mut Deserializer::new(&packed_v1[PACK_HEADER_LEN..])).unwrap();
+        let mut legacy = Vec::new();
+        pack.games
+            .serialize(&mut Serializer::new(&mut legacy))
+            .unwrap();
+        let (metadata, unpacked) = read_pack(&legacy).unwrap();
+        assert_eq!(metadata.version, 0);
+        assert_eq!(metadata.game_count, 1);
+        assert_eq!(unpacked["test/game"].to_game(), games["test/game"]);
+
+        // version 2 packs predate board sizes, setup stones and passes
+        let game = &games["test/game"];
+        let pack = PackV2 {
+            metadata: PackMetadata {
+                version: 2,
+                created_at: 0,
+                game_count: 1,
+                source: "test source".to_string(),
             },
+            strings: vec![
+                game.event.clone(),
+                game.round.clone(),
+                game.location.clone(),
+            ],
+            players: vec![game.player_black.clone(), game.player_white.clone()],
+            has_captures: false,
+            games: vec![PackedGameV2 {
+                name: "test/game".to_string(),
+                event: 0,
+                round: 1,
+                location: 2,
+                date: game.date.clone(),
+                player_black: 0,
+                player_white: 1,
+                rank_black: game.rank_black.clone(),
+                rank_white: game.rank_white.clone(),
+                komi: game.komi,
+                rules: game.rules.clone(),
+                result: game.result.clone(),
+                moves: pack_placements(&game.moves),
+                captures: Vec::new(),
+            }],
+        };
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        pack.serialize(&mut Serializer::new(&mut encoder)).unwrap();
+        let packed_v2 = write_pack(2, &encoder.finish().unwrap());
+        let (metadata, unpacked) = read_pack(&packed_v2).unwrap();
+        assert_eq!(metadata.version, 2);
+        assert_eq!(metadata.source, "test source");
+        assert_eq!(unpacked["test/game"].to_game(), *game);
+
+        let mut corrupted = packed.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            read_pack(&corrupted),
+            Err(PackError::ChecksumMismatch { .. })
+        ));
+
+        let mut future = packed.clone();
+        future[5] = 99;
+        assert!(matches!(
+            read_pack(&future),
+            Err(PackError::UnsupportedVersion(99))
+        ));
+
+        assert!(matches!(read_pack(&packed[..6]), Err(PackError::Truncated)));
+    }
+
+    proptest! {
+        #[test]
+        fn test_get_mirrored_property(position in prop::collection::vec(
+            (any::<bool>(), any::<u8>(), any::<u8>()),
+            0..100
+        ).prop_map(|v| v.into_iter().map(|(is_black, x, y)| {
             Placement {
-                color: Color::White,
-                point: Point { x: 7, y: 3 },
+                color: if is_black { Color::Black } else { Color::White },
+                point: Point {
+                    x: x % BOARD_SIZE,
+                    y: y % BOARD_SIZE
+                }
             }
-        ]));
+        }).collect::<Vec<_>>())) {
+            let mirrored = get_mirrored(&position, BOARD_SIZE);
+            let double_mirrored = get_mirrored(&mirrored, BOARD_SIZE);
+            assert_eq!(position, double_mirrored);
+        }
+    }
 
-        // Placements in different quadrants
-        assert!(!check_within_one_quadrant(&[
+    #[test]
+    fn test_match_game_moves() {
+        let moves = vec![
             Placement {
                 color: Color::Black,
-                point: Point { x: 5, y: 5 }, // Top-left
+                point: Point { x: 3, y: 3 },
             },
             Placement {
                 color: Color::White,
-                point: Point { x: 15, y: 15 }, // Bottom-right
-            }
-        ]));
+                point: Point { x: 15, y: 15 },
+            },
+            Placement {
+                color: Color::Black,
+                point: Point { x: 2, y: 5 },
+            },
+        ];
+        let position = vec![moves[2], moves[0]];
+        assert_eq!(
+            match_game_moves(&position, &[], &moves),
+            Some(vec![Some(2), Some(0)])
+        );
+        assert_eq!(match_game(&position, &[], &moves), Some(Some(2)));
+
+        let inverted = switch_colors(&position);
+        assert_eq!(match_game_moves(&inverted, &[], &moves), None);
+        assert_eq!(match_game_moves(&[], &[], &moves), Some(vec![]));
+
+        // setup stones are on the board before move 1
+        let setup = inverted.clone();
+        assert_eq!(
+            match_game_moves(&inverted, &setup, &moves),
+            Some(vec![None, None])
+        );
+        assert_eq!(match_game(&inverted, &setup, &moves), Some(None));
+        let mixed = vec![inverted[0], moves[1]];
+        assert_eq!(match_game(&mixed, &setup, &moves), Some(Some(1)));
+        let packed = pack_placements(&moves);
+        assert_eq!(
+            match_packed_game_moves(&mixed, &setup, &PackedPlacements::new(&packed)),
+            Some(vec![None, Some(1)])
+        );
+    }
+
+    #[test]
+    fn test_check_within_one_quadrant() {
+        // Empty position
+        assert!(check_within_one_quadrant(&[], BOARD_SIZE));
+
+        // Single placement in top-left quadrant
+        assert!(check_within_one_quadrant(
+            &[Placement {
+                color: Color::Black,
+                point: Point { x: 5, y: 5 },
+            }],
+            BOARD_SIZE
+        ));
+
+        // Multiple placements in top-left quadrant
+        assert!(check_within_one_quadrant(
+            &[
+                Placement {
+                    color: Color::Black,
+                    point: Point { x: 5, y: 5 },
+                },
+                Placement {
+                    color: Color::White,
+                    point: Point { x: 7, y: 3 },
+                }
+            ],
+            BOARD_SIZE
+        ));
+
+        // Placements in different quadrants
+        assert!(!check_within_one_quadrant(
+            &[
+                Placement {
+                    color: Color::Black,
+                    point: Point { x: 5, y: 5 }, // Top-left
+                },
+                Placement {
+                    color: Color::White,
+                    point: Point { x: 15, y: 15 }, // Bottom-right
+                }
+            ],
+            BOARD_SIZE
+        ));
 
         // Placements on middle lines
-        assert!(!check_within_one_quadrant(&[Placement {
-            color: Color::Black,
-            point: Point { x: 9, y: 5 }, // On vertical middle line
-        }]));
-        assert!(!check_within_one_quadrant(&[Placement {
-            color: Color::Black,
-            point: Point { x: 5, y: 9 }, // On horizontal middle line
-        }]));
-        assert!(!check_within_one_quadrant(&[Placement {
-            color: Color::Black,
-            point: Point { x: 9, y: 9 }, // On both middle lines
-        }]));
+        assert!(!check_within_one_quadrant(
+            &[Placement {
+                color: Color::Black,
+                point: Point { x: 9, y: 5 }, // On vertical middle line
+            }],
+            BOARD_SIZE
+        ));
+        assert!(!check_within_one_quadrant(
+            &[Placement {
+                color: Color::Black,
+                point: Point { x: 5, y: 9 }, // On horizontal middle line
+            }],
+            BOARD_SIZE
+        ));
+        assert!(!check_within_one_quadrant(
+            &[Placement {
+                color: Color::Black,
+                point: Point { x: 9, y: 9 }, // On both middle lines
+            }],
+            BOARD_SIZE
+        ));
+
+        // Smaller boards
+        let position = |x, y| {
+            [Placement {
+                color: Color::Black,
+                point: Point { x, y },
+            }]
+        };
+        assert!(check_within_one_quadrant(&position(2, 2), 9));
+        assert!(!check_within_one_quadrant(&position(4, 2), 9));
+        assert!(check_within_one_quadrant(&position(3, 9), 13));
+        assert!(!check_within_one_quadrant(&position(6, 2), 13));
+        // even sizes have no middle lines
+        assert!(check_within_one_quadrant(&position(4, 4), 8));
     }
 
     #[test]
     fn test_get_connected_groups() {
         // Test empty position
-        assert!(get_connected_groups(&[]).is_empty());
+        assert!(get_connected_groups(&[], BOARD_SIZE).is_empty());
 
         // Test single stone
         let single_stone = vec![Placement {
             color: Color::Black,
             point: Point { x: 5, y: 5 },
         }];
-        let groups = get_connected_groups(&single_stone);
+        let groups = get_connected_groups(&single_stone, BOARD_SIZE);
         assert_eq!(groups.len(), 1);
         assert_eq!(groups[0].len(), 1);
 
@@ -1329,7 +2671,7 @@ mod tests {
                 point: Point { x: 5, y: 6 },
             },
         ];
-        let groups = get_connected_groups(&connected_stones);
+        let groups = get_connected_groups(&connected_stones, BOARD_SIZE);
         assert_eq!(groups.len(), 1);
         assert_eq!(groups[0].len(), 2);
 
@@ -1352,7 +2694,7 @@ mod tests {
                 point: Point { x: 10, y: 11 },
             },
         ];
-        let groups = get_connected_groups(&separate_groups);
+        let groups = get_connected_groups(&separate_groups, BOARD_SIZE);
         assert_eq!(groups.len(), 2);
         assert_eq!(groups[0].len(), 2);
         assert_eq!(groups[1].len(), 2);
@@ -1368,7 +2710,7 @@ mod tests {
                 point: Point { x: 6, y: 6 },
             },
         ];
-        let groups = get_connected_groups(&diagonal_stones);
+        let groups = get_connected_groups(&diagonal_stones, BOARD_SIZE);
         assert_eq!(groups.len(), 2);
         assert_eq!(groups[0].len(), 1);
         assert_eq!(groups[1].len(), 1);
@@ -1381,8 +2723,8 @@ mod tests {
             color: Color::Black,
             point: Point { x: 5, y: 5 },
         }];
-        let groups = get_connected_groups(&position);
-        let liberties = get_group_liberties(&groups[0], &position);
+        let groups = get_connected_groups(&position, BOARD_SIZE);
+        let liberties = get_group_liberties(&groups[0], &position, BOARD_SIZE);
         assert_eq!(liberties.len(), 4);
 
         // Test surrounded stone (no liberties)
@@ -1408,8 +2750,8 @@ mod tests {
                 point: Point { x: 6, y: 5 },
             },
         ];
-        let groups = get_connected_groups(&surrounded);
-        let liberties = get_group_liberties(&groups[0], &surrounded);
+        let groups = get_connected_groups(&surrounded, BOARD_SIZE);
+        let liberties = get_group_liberties(&groups[0], &surrounded, BOARD_SIZE);
         assert_eq!(liberties.len(), 0);
 
         // Test group with shared liberties
@@ -1423,8 +2765,8 @@ mod tests {
                 point: Point { x: 5, y: 6 },
             },
         ];
-        let groups = get_connected_groups(&group_with_shared_liberties);
-        let liberties = get_group_liberties(&groups[0], &group_with_shared_liberties);
+        let groups = get_connected_groups(&group_with_shared_liberties, BOARD_SIZE);
+        let liberties = get_group_liberties(&groups[0], &group_with_shared_liberties, BOARD_SIZE);
         assert_eq!(liberties.len(), 6); // 2 stones × 4 liberties - 2 shared liberties
     }
 
@@ -1441,7 +2783,7 @@ mod tests {
                 point: Point { x: 10, y: 10 },
             },
         ];
-        let captured = get_captured_groups(&position);
+        let captured = get_captured_groups(&position, BOARD_SIZE);
         assert!(captured.is_empty());
 
         // Test captured group
@@ -1467,7 +2809,7 @@ mod tests {
                 point: Point { x: 6, y: 5 },
             },
         ];
-        let captured = get_captured_groups(&position_with_capture);
+        let captured = get_captured_groups(&position_with_capture, BOARD_SIZE);
         assert_eq!(captured.len(), 1);
         assert_eq!(captured[0].len(), 1);
         assert_eq!(captured[0][0].color, Color::Black);
@@ -1517,7 +2859,7 @@ mod tests {
                 point: Point { x: 16, y: 15 },
             },
         ];
-        let captured = get_captured_groups(&multiple_captures);
+        let captured = get_captured_groups(&multiple_captures, BOARD_SIZE);
         assert_eq!(captured.len(), 2);
         assert_eq!(captured[0].len(), 1);
         assert_eq!(captured[1].len(), 1);
@@ -1529,7 +2871,7 @@ mod tests {
     fn test_calculate_position() {
         // Test empty game
         let empty_game = vec![];
-        let state = calculate_position(&empty_game);
+        let state = calculate_position(&empty_game, BOARD_SIZE);
         assert!(state.position.is_empty());
         assert!(state.captures.is_empty());
         assert_eq!(state.number_of_moves, 0);
@@ -1566,7 +2908,7 @@ mod tests {
             },
         ];
 
-        let state = calculate_position(&capture_sequence);
+        let state = calculate_position(&capture_sequence, BOARD_SIZE);
 
         // Check final state
         assert_eq!(state.position.len(), 5); // 5 white stones
@@ -1621,7 +2963,7 @@ mod tests {
             },
         ];
 
-        let state = calculate_position(&multiple_captures);
+        let state = calculate_position(&multiple_captures, BOARD_SIZE);
 
         // Check final state
         assert_eq!(state.position.len(), 8); // 8 white stones
@@ -1632,7 +2974,7 @@ mod tests {
 
     #[test]
     fn test_goboard_new() {
-        let board = GoBoard::new();
+        let board = GoBoard::new(BOARD_SIZE);
         assert!(board.position.is_empty());
         assert!(board.captures.is_empty());
         assert!(board.groups.is_empty());
@@ -1640,7 +2982,7 @@ mod tests {
 
     #[test]
     fn test_goboard_single_move() {
-        let mut board = GoBoard::new();
+        let mut board = GoBoard::new(BOARD_SIZE);
         let move_ = Placement {
             color: Color::Black,
             point: Point { x: 5, y: 5 },
@@ -1654,7 +2996,7 @@ mod tests {
 
     #[test]
     fn test_goboard_connected_group() {
-        let mut board = GoBoard::new();
+        let mut board = GoBoard::new(BOARD_SIZE);
         let move1 = Placement {
             color: Color::Black,
             point: Point { x: 5, y: 5 },
@@ -1674,7 +3016,7 @@ mod tests {
 
     #[test]
     fn test_goboard_capture() {
-        let mut board = GoBoard::new();
+        let mut board = GoBoard::new(BOARD_SIZE);
         // Place a white stone
         board.make_move(&Placement {
             color: Color::White,
@@ -1705,9 +3047,108 @@ mod tests {
         assert_eq!(board.captures[0][0].point, Point { x: 5, y: 5 });
     }
 
+    #[test]
+    fn test_goboard_with_setup() {
+        let setup = [
+            Placement {
+                color: Color::Black,
+                point: Point { x: 3, y: 3 },
+            },
+            Placement {
+                color: Color::Black,
+                point: Point { x: 3, y: 4 },
+            },
+        ];
+        let board = GoBoard::with_setup(BOARD_SIZE, &setup);
+        assert_eq!(board.position, setup.to_vec());
+        assert_eq!(board.groups, vec![setup.to_vec()]);
+        assert!(board.captures.is_empty());
+    }
+
+    #[test]
+    fn test_passes() {
+        let pass = |color| Placement {
+            color,
+            point: Point::PASS,
+        };
+        let moves = [
+            Placement {
+                color: Color::Black,
+                point: Point { x: 0, y: 1 },
+            },
+            pass(Color::White),
+            Placement {
+                color: Color::Black,
+                point: Point { x: 1, y: 0 },
+            },
+            pass(Color::White),
+            pass(Color::Black),
+        ];
+        let setup = [Placement {
+            color: Color::White,
+            point: Point { x: 0, y: 0 },
+        }];
+
+        let packed = pack_placements(&moves);
+        assert_eq!(unpack_placements(&packed).0, moves.to_vec());
+        let packed_moves = PackedPlacements::new(&packed);
+        assert_eq!(packed_moves.get(4), Some(pass(Color::Black)));
+        assert_eq!(packed_moves.position(&pass(Color::White)), Some(1));
+
+        // passes are kept in place by every transform
+        for rotated in all_rotations(&moves, 9) {
+            assert_eq!(rotated[1], pass(Color::White));
+        }
+        assert_eq!(get_mirrored(&moves, BOARD_SIZE)[3], pass(Color::White));
+
+        // passes take up a move number without changing the board
+        assert_eq!(
+            compute_captures(&setup, &moves, BOARD_SIZE),
+            HashMap::from([(2, setup.to_vec())])
+        );
+        let mut board = GoBoard::new(BOARD_SIZE);
+        assert!(board.make_move(&pass(Color::Black)).is_empty());
+        assert!(board.position.is_empty());
+        assert_eq!(calculate_position(&moves, BOARD_SIZE).position.len(), 2);
+        assert_eq!(match_game(&moves[2..3], &setup, &moves), Some(Some(2)));
+    }
+
+    #[test]
+    fn test_goboard_small_board_capture() {
+        let moves = [
+            Placement {
+                color: Color::White,
+                point: Point { x: 8, y: 8 },
+            },
+            Placement {
+                color: Color::Black,
+                point: Point { x: 7, y: 8 },
+            },
+            Placement {
+                color: Color::Black,
+                point: Point { x: 8, y: 7 },
+            },
+        ];
+        // the corner of a 9x9 board is the middle of a 19x19 board
+        let mut board = GoBoard::new(9);
+        let mut large_board = GoBoard::new(BOARD_SIZE);
+        for move_ in &moves {
+            board.make_move(move_);
+            large_board.make_move(move_);
+        }
+        assert_eq!(board.captures, vec![vec![moves[0]]]);
+        assert!(large_board.captures.is_empty());
+        assert_eq!(
+            compute_captures(&[], &moves, 9),
+            HashMap::from([(2, vec![moves[0]])])
+        );
+        assert_eq!(calculate_position(&moves, 9).captures, vec![moves[0]]);
+        assert!(calculate_position(&moves, BOARD_SIZE).captures.is_empty());
+    }
+
     #[test]
     fn test_goboard_suicide_move() {
-        let mut board = GoBoard::new();
+        let mut board = GoBoard::new(BOARD_SIZE);
         // Place surrounding stones
         board.make_move(&Placement {
             color: Color::White,
@@ -1740,7 +3181,7 @@ mod tests {
 
     #[test]
     fn test_goboard_multiple_groups() {
-        let mut board = GoBoard::new();
+        let mut board = GoBoard::new(BOARD_SIZE);
         // Create two separate black groups
         board.make_move(&Placement {
             color: Color::Black,
@@ -1766,7 +3207,7 @@ mod tests {
 
     #[test]
     fn test_goboard_merge_groups() {
-        let mut board = GoBoard::new();
+        let mut board = GoBoard::new(BOARD_SIZE);
         // Create two black groups
         board.make_move(&Placement {
             color: Color::Black,
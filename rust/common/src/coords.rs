@@ -0,0 +1,424 @@
+//! Conversions between `Point`s and the usual ways of writing Go positions
+//!
+//! * GTP vertices, `Q16`, with the columns lettered from the left skipping `I` and the rows
+//!   numbered from the bottom
+//! * SGF points, `pd`, with the column then the row lettered from the top left
+//! * ASCII diagrams in the style of Sensei's Library
+
+use crate::baduk::{Color, Placement, Point};
+use std::fmt;
+
+const GTP_COLUMNS: &[u8] = b"ABCDEFGHJKLMNOPQRST";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoordinateError {
+    InvalidPoint(String),
+    OutsideBoard(String),
+    UnexpectedCharacter { character: char, row: usize }, // row of the diagram, starting at 1
+    DiagramTooLarge,
+}
+
+impl fmt::Display for CoordinateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoordinateError::InvalidPoint(s) => write!(f, "invalid point '{s}'"),
+            CoordinateError::OutsideBoard(s) => write!(f, "point '{s}' is outside the board"),
+            CoordinateError::UnexpectedCharacter { character, row } => {
+                write!(f, "unexpected '{character}' in diagram row {row}")
+            }
+            CoordinateError::DiagramTooLarge => write!(f, "diagram is larger than the board"),
+        }
+    }
+}
+
+impl std::error::Error for CoordinateError {}
+
+/// Parses a GTP vertex like `Q16` or `d4`, whose rows are numbered from the bottom of a board
+/// of `board_size`
+pub fn parse_gtp_point(vertex: &str, board_size: u8) -> Result<Point, CoordinateError> {
+    let invalid = || CoordinateError::InvalidPoint(vertex.to_string());
+    let mut chars = vertex.chars();
+    let column = chars.next().ok_or_else(invalid)?.to_ascii_uppercase();
+    let row: u8 = chars.as_str().parse().map_err(|_| invalid())?;
+    if !column.is_ascii_alphabetic() || column == 'I' {
+        return Err(invalid());
+    }
+
+    match GTP_COLUMNS.iter().position(|&c| c as char == column) {
+        Some(x) if x < board_size as usize && (1..=board_size).contains(&row) => Ok(Point {
+            x: x as u8,
+            y: board_size - row,
+        }),
+        _ => Err(CoordinateError::OutsideBoard(vertex.to_string())),
+    }
+}
+
+/// Formats a point as a GTP vertex, or `pass` for `Point::PASS`
+pub fn format_gtp_point(point: &Point, board_size: u8) -> String {
+    if point.is_pass() {
+        return "pass".to_string();
+    }
+    format!(
+        "{}{}",
+        GTP_COLUMNS[point.x as usize] as char,
+        board_size - point.y
+    )
+}
+
+/// Parses an SGF point like `pd` on a board of `board_size`
+pub fn parse_sgf_point(value: &str, board_size: u8) -> Result<Point, CoordinateError> {
+    let coordinate = |c: u8| {
+        let n = c.wrapping_sub(b'a');
+        (n < board_size).then_some(n)
+    };
+    match value.as_bytes() {
+        [x, y] if x.is_ascii_lowercase() && y.is_ascii_lowercase() => {
+            match (coordinate(*x), coordinate(*y)) {
+                (Some(x), Some(y)) => Ok(Point { x, y }),
+                _ => Err(CoordinateError::OutsideBoard(value.to_string())),
+            }
+        }
+        _ => Err(CoordinateError::InvalidPoint(value.to_string())),
+    }
+}
+
+pub fn format_sgf_point(point: &Point) -> String {
+    [b'a' + point.x, b'a' + point.y]
+        .iter()
+        .map(|&c| c as char)
+        .collect()
+}
+
+fn is_border(line: &str) -> bool {
+    let line = line.trim();
+    line.contains('-') && line.chars().all(|c| matches!(c, '-' | '+' | '|' | ' '))
+}
+
+/// Parses an ASCII diagram in the style of Sensei's Library
+///
+/// ```text
+/// $$B Counting from the top left
+/// $$ +-----------
+/// $$ | . . . . .
+/// $$ | . . . 1 .
+/// $$ | . . O . .
+/// $$ | . . . X .
+/// ```
+///
+/// The `$$` prefixes and the header line are optional. Black stones are `X`, `B`, `#`, `Y`
+/// or `Z`, white stones `O`, `W`, `@`, `Q` or `P`, and numbers are moves played in that order
+/// from the color of the header, black by default. All other marks, lowercase labels like `x`
+/// and `o` among them, are empty points. The borders place the diagram on the board, a diagram
+/// without a left or top border is placed against the right or bottom edge if it has that
+/// border, and at the top left otherwise.
+///
+/// Returns the stones row by row, followed by the numbered moves.
+pub fn parse_diagram(diagram: &str, board_size: u8) -> Result<Vec<Placement>, CoordinateError> {
+    let mut first_color = Color::Black;
+    let mut lines = Vec::new();
+    for line in diagram.lines() {
+        let line = match line.trim_start().strip_prefix("$$") {
+            // the header, `$$B`, `$$W` or with flags and a title like `$$Bcm12 Joseki`
+            Some(header)
+                if lines.is_empty() && header.starts_with(|c: char| !c.is_whitespace()) =>
+            {
+                if header.starts_with('W') {
+                    first_color = Color::White;
+                }
+                continue;
+            }
+            Some(line) => line,
+            None => line,
+        };
+        if !line.trim().is_empty() {
+            lines.push(line);
+        }
+    }
+
+    let has_top = lines.first().is_some_and(|line| is_border(line));
+    let has_bottom = lines.len() > 1 && lines.last().is_some_and(|line| is_border(line));
+    let rows: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|line| !is_border(line))
+        .collect();
+    let has_left = rows.iter().any(|row| row.trim_start().starts_with('|'));
+    let has_right = rows.iter().any(|row| row.trim_end().ends_with('|'));
+
+    let mut stones = Vec::new();
+    let mut moves = Vec::new();
+    let mut width = 0;
+    for (y, row) in rows.iter().enumerate() {
+        let cells = row.chars().filter(|c| !c.is_whitespace() && *c != '|');
+        let mut x = 0;
+        for cell in cells {
+            let color = match cell {
+                'X' | 'B' | '#' | 'Y' | 'Z' | '●' => Some(Color::Black),
+                'O' | 'W' | '@' | 'Q' | 'P' | '○' => Some(Color::White),
+                '0'..='9' => {
+                    // 0 is the tenth move
+                    let number = cell.to_digit(10).unwrap().checked_sub(1).unwrap_or(9);
+                    moves.push((number, x, y));
+                    None
+                }
+                '.' | ',' | '+' | '-' | '_' | 'C' | 'S' | 'T' | 'M' | 'a'..='z' => None,
+                _ => {
+                    return Err(CoordinateError::UnexpectedCharacter {
+                        character: cell,
+                        row: y + 1,
+                    });
+                }
+            };
+            if let Some(color) = color {
+                stones.push((color, x, y));
+            }
+            x += 1;
+        }
+        width = width.max(x);
+    }
+
+    let height = rows.len();
+    if width > board_size as usize || height > board_size as usize {
+        return Err(CoordinateError::DiagramTooLarge);
+    }
+    let x_offset = if has_right && !has_left {
+        board_size as usize - width
+    } else {
+        0
+    };
+    let y_offset = if has_bottom && !has_top {
+        board_size as usize - height
+    } else {
+        0
+    };
+    let point = |x: usize, y: usize| Point {
+        x: (x + x_offset) as u8,
+        y: (y + y_offset) as u8,
+    };
+
+    moves.sort_by_key(|(number, _, _)| *number);
+    let other_color = match first_color {
+        Color::Black => Color::White,
+        Color::White => Color::Black,
+    };
+    let placements = stones
+        .into_iter()
+        .map(|(color, x, y)| Placement {
+            color,
+            point: point(x, y),
+        })
+        .chain(moves.into_iter().map(|(number, x, y)| Placement {
+            color: if number % 2 == 0 {
+                first_color
+            } else {
+                other_color
+            },
+            point: point(x, y),
+        }))
+        .collect();
+    Ok(placements)
+}
+
+/// Star points of a board, the 4-4 points from 13x13 up and the 3-3 points below that, the
+/// center of odd sizes, and the sides from 15x15 up
+fn hoshi(board_size: u8) -> Vec<(u8, u8)> {
+    if board_size < 7 {
+        return Vec::new();
+    }
+    let near = if board_size >= 13 { 3 } else { 2 };
+    let far = board_size - near - 1;
+    let mid = board_size / 2;
+    let mut points = vec![(near, near), (far, near), (near, far), (far, far)];
+    if board_size % 2 == 1 {
+        points.push((mid, mid));
+        if board_size >= 15 {
+            points.extend([(mid, near), (near, mid), (far, mid), (mid, far)]);
+        }
+    }
+    points
+}
+
+/// Formats a position as a full board diagram in the style of Sensei's Library, later
+/// placements on the same point replace earlier ones
+pub fn format_diagram(position: &[Placement], board_size: u8) -> String {
+    let size = board_size as usize;
+    let hoshi = hoshi(board_size);
+    let mut board = vec![vec![None; size]; size];
+    for placement in position {
+        board[placement.point.y as usize][placement.point.x as usize] = Some(placement.color);
+    }
+
+    let border = format!("$$ +{}+\n", "-".repeat(size * 2 + 1));
+    let mut diagram = border.clone();
+    for (y, row) in board.iter().enumerate() {
+        let cells: Vec<&str> = row
+            .iter()
+            .enumerate()
+            .map(|(x, cell)| match cell {
+                Some(Color::Black) => "X",
+                Some(Color::White) => "O",
+                None if hoshi.contains(&(x as u8, y as u8)) => ",",
+                None => ".",
+            })
+            .collect();
+        diagram.push_str(&format!("$$ | {} |\n", cells.join(" ")));
+    }
+    diagram.push_str(&border);
+    diagram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baduk::BOARD_SIZE;
+
+    fn placement(color: Color, x: u8, y: u8) -> Placement {
+        Placement {
+            color,
+            point: Point { x, y },
+        }
+    }
+
+    #[test]
+    fn test_points() {
+        let point = Point { x: 15, y: 3 };
+        assert_eq!(parse_gtp_point("Q16", BOARD_SIZE), Ok(point));
+        assert_eq!(parse_gtp_point("q16", BOARD_SIZE), Ok(point));
+        assert_eq!(format_gtp_point(&point, BOARD_SIZE), "Q16");
+        assert_eq!(parse_gtp_point("J1", BOARD_SIZE), Ok(Point { x: 8, y: 18 }));
+        assert_eq!(parse_gtp_point("A19", BOARD_SIZE), Ok(Point { x: 0, y: 0 }));
+        assert!(matches!(
+            parse_gtp_point("I5", BOARD_SIZE),
+            Err(CoordinateError::InvalidPoint(_))
+        ));
+        assert!(matches!(
+            parse_gtp_point("U5", BOARD_SIZE),
+            Err(CoordinateError::OutsideBoard(_))
+        ));
+        assert!(parse_gtp_point("A20", BOARD_SIZE).is_err());
+        assert!(parse_gtp_point("A0", BOARD_SIZE).is_err());
+        assert!(parse_gtp_point("16", BOARD_SIZE).is_err());
+        assert_eq!(parse_gtp_point("E5", 9), Ok(Point { x: 4, y: 4 }));
+        assert_eq!(format_gtp_point(&Point { x: 8, y: 0 }, 9), "J9");
+        assert!(matches!(
+            parse_gtp_point("K1", 9),
+            Err(CoordinateError::OutsideBoard(_))
+        ));
+        assert!(parse_gtp_point("A10", 9).is_err());
+        assert_eq!(parse_sgf_point("ii", 9), Ok(Point { x: 8, y: 8 }));
+        assert!(matches!(
+            parse_sgf_point("pd", 9),
+            Err(CoordinateError::OutsideBoard(_))
+        ));
+
+        assert_eq!(parse_sgf_point("pd", BOARD_SIZE), Ok(point));
+        assert_eq!(format_sgf_point(&point), "pd");
+        assert_eq!(format_sgf_point(&Point::PASS), "tt");
+        assert_eq!(format_gtp_point(&Point::PASS, 9), "pass");
+        assert!(matches!(
+            parse_sgf_point("zz", BOARD_SIZE),
+            Err(CoordinateError::OutsideBoard(_))
+        ));
+        assert!(matches!(
+            parse_sgf_point("PD", BOARD_SIZE),
+            Err(CoordinateError::InvalidPoint(_))
+        ));
+
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                let point = Point { x, y };
+                assert_eq!(
+                    parse_gtp_point(&format_gtp_point(&point, BOARD_SIZE), BOARD_SIZE),
+                    Ok(point)
+                );
+                assert_eq!(
+                    parse_sgf_point(&format_sgf_point(&point), BOARD_SIZE),
+                    Ok(point)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_diagrams() {
+        let top_left = "
+            $$W A joseki
+            $$ +-----------
+            $$ | . . . . .
+            $$ | . . . 2 .
+            $$ | . . 1 . .
+            $$ | . . . X .
+        ";
+        assert_eq!(
+            parse_diagram(top_left, BOARD_SIZE),
+            Ok(vec![
+                placement(Color::Black, 3, 3),
+                placement(Color::White, 2, 2),
+                placement(Color::Black, 3, 1),
+            ])
+        );
+
+        let bottom_right = "
+            . x O o |
+            . X a . |
+            --------+
+        ";
+        assert_eq!(
+            parse_diagram(bottom_right, BOARD_SIZE),
+            Ok(vec![
+                placement(Color::White, 17, 17),
+                placement(Color::Black, 16, 18),
+            ])
+        );
+
+        // marked stones
+        assert_eq!(
+            parse_diagram("Z P\nY Q", BOARD_SIZE),
+            Ok(vec![
+                placement(Color::Black, 0, 0),
+                placement(Color::White, 1, 0),
+                placement(Color::Black, 0, 1),
+                placement(Color::White, 1, 1),
+            ])
+        );
+        assert_eq!(
+            parse_diagram(". . .\n. X ?", BOARD_SIZE),
+            Err(CoordinateError::UnexpectedCharacter {
+                character: '?',
+                row: 2
+            })
+        );
+        assert_eq!(
+            parse_diagram(&". ".repeat(20), BOARD_SIZE),
+            Err(CoordinateError::DiagramTooLarge)
+        );
+
+        let position = vec![
+            placement(Color::Black, 3, 3),
+            placement(Color::White, 15, 16),
+            placement(Color::Black, 0, 18),
+        ];
+        let diagram = format_diagram(&position, BOARD_SIZE);
+        assert_eq!(diagram.lines().count(), 21);
+        assert_eq!(
+            diagram.lines().nth(4),
+            Some("$$ | . . . X . . . . . , . . . . . , . . . |")
+        );
+        assert_eq!(parse_diagram(&diagram, BOARD_SIZE), Ok(position));
+
+        let position = vec![placement(Color::Black, 2, 6), placement(Color::White, 8, 8)];
+        let diagram = format_diagram(&position, 9);
+        assert_eq!(diagram.lines().count(), 11);
+        assert_eq!(diagram.lines().nth(5), Some("$$ | . . . . , . . . . |"));
+        assert_eq!(diagram.lines().nth(3), Some("$$ | . . , . . . , . . |"));
+        assert_eq!(diagram.lines().nth(7), Some("$$ | . . X . . . , . . |"));
+        assert_eq!(parse_diagram(&diagram, 9), Ok(position));
+        assert_eq!(
+            parse_diagram(bottom_right, 9),
+            Ok(vec![
+                placement(Color::White, 7, 7),
+                placement(Color::Black, 6, 8),
+            ])
+        );
+    }
+}
@@ -1 +1,7 @@
+pub mod archive;
 pub mod baduk;
+pub mod coords;
+pub mod sgf;
+pub mod store;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
@@ -0,0 +1,399 @@
+//! Writing games as SGF, to take them and search results to other Go software
+
+use crate::baduk::{
+    Color, Game, GameResult, GameTree, Placement, Player, Point, Rules, Score, SgfDate,
+};
+use crate::coords::format_sgf_point;
+
+/// Comment on the node where a search matched the game
+const MATCH_COMMENT: &str = "The search position is matched here";
+
+/// Markup showing where a search matched a game
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchMarkup {
+    pub points: Vec<Point>, // the stones of the game that matched the query, marked with `TR`
+    pub last_move_matched: Option<usize>, // the move it was matched at, None for the setup
+}
+
+/// Writes a game as an SGF game tree with its metadata, setup stones and moves, and the
+/// variations and comments of its tree when it kept them
+///
+/// # Arguments
+/// * `markup` - Where a search matched the game, marked on the node of `last_move_matched`
+pub fn write_sgf(game: &Game, markup: Option<&MatchMarkup>) -> String {
+    let mut sgf = format!("(;GM[1]FF[4]CA[UTF-8]SZ[{}]", game.board_size);
+    let properties = [
+        ("EV", game.event.clone()),
+        ("RO", game.round.clone()),
+        ("PC", game.location.clone()),
+        (
+            "DT",
+            game.date.as_ref().map(format_date).unwrap_or_default(),
+        ),
+        ("PB", player_name(&game.player_black).to_string()),
+        ("BR", game.rank_black.to_string()),
+        ("PW", player_name(&game.player_white).to_string()),
+        ("WR", game.rank_white.to_string()),
+        (
+            "KM",
+            game.komi.map(|komi| komi.to_string()).unwrap_or_default(),
+        ),
+        (
+            "RU",
+            game.rules.as_ref().map(format_rules).unwrap_or_default(),
+        ),
+        ("RE", format_result(&game.result)),
+    ];
+    for (id, value) in properties {
+        if !value.is_empty() {
+            sgf.push_str(&format!("{id}[{}]", escape_text(&value)));
+        }
+    }
+    if game.handicap > 0 {
+        sgf.push_str(&format!("HA[{}]", game.handicap));
+    }
+    // other software can't tell who plays first after setup stones without `PL`
+    if !game.setup.is_empty() || game.moves.first().map(|m| m.color) != Some(game.first_color) {
+        sgf.push_str(&format!("PL[{}]", color_letter(game.first_color)));
+    }
+    push_setup(&mut sgf, &game.setup);
+    let empty_tree = GameTree::default();
+    let tree = game.tree.as_ref().unwrap_or(&empty_tree);
+    match markup.filter(|markup| markup.last_move_matched.is_none()) {
+        Some(markup) => push_markup(&mut sgf, tree.root_comment.as_ref(), markup),
+        None => {
+            if let Some(comment) = &tree.root_comment {
+                sgf.push_str(&format!("C[{}]", escape_text(comment)));
+            }
+        }
+    }
+
+    let branches = alternatives(tree, None, 0);
+    if branches.is_empty() {
+        write_line(&mut sgf, tree, None, &game.moves, 0, markup);
+    } else {
+        write_branches(&mut sgf, tree, None, &game.moves, 0, &branches, markup);
+    }
+    sgf.push_str(")\n");
+    sgf
+}
+
+/// Writes the moves of a line, numbered from move `first` of the game. The variations replacing
+/// its first move are written by the caller.
+///
+/// # Arguments
+/// * `parent` - The variation of the line, None for the moves of the game
+fn write_line(
+    sgf: &mut String,
+    tree: &GameTree,
+    parent: Option<usize>,
+    moves: &[Placement],
+    first: usize,
+    markup: Option<&MatchMarkup>,
+) {
+    let (comments, start) = match parent {
+        Some(variation) => (
+            &tree.variations[variation].comments,
+            tree.variations[variation].start,
+        ),
+        None => (&tree.comments, 0),
+    };
+    for (i, placement) in moves.iter().enumerate() {
+        let number = first + i;
+        if i > 0 {
+            let branches = alternatives(tree, parent, number);
+            if !branches.is_empty() {
+                write_branches(sgf, tree, parent, &moves[i..], number, &branches, markup);
+                return;
+            }
+        }
+
+        if parent.is_none() {
+            push_added_setup(sgf, tree, number);
+        }
+        sgf.push_str(&format!(";{}[", color_letter(placement.color)));
+        if !placement.point.is_pass() {
+            sgf.push_str(&format_sgf_point(&placement.point));
+        }
+        sgf.push(']');
+        let comment = comments.get(&(number - start));
+        match markup.filter(|markup| markup.last_move_matched == Some(number)) {
+            Some(markup) => push_markup(sgf, comment, markup),
+            None => {
+                if let Some(comment) = comment {
+                    sgf.push_str(&format!("C[{}]", escape_text(comment)));
+                }
+            }
+        }
+    }
+
+    let number = first + moves.len();
+    if parent.is_none() {
+        push_added_setup(sgf, tree, number);
+    }
+    let branches = alternatives(tree, parent, number);
+    write_branches(sgf, tree, parent, &[], number, &branches, markup);
+}
+
+/// Writes the stones added during the game after `played` moves as a node of their own
+fn push_added_setup(sgf: &mut String, tree: &GameTree, played: usize) {
+    if let Some(setup) = tree.setup.get(&played) {
+        sgf.push(';');
+        push_setup(sgf, setup);
+    }
+}
+
+/// Writes the rest of a line and the variations replacing its next move as sibling game trees
+fn write_branches(
+    sgf: &mut String,
+    tree: &GameTree,
+    parent: Option<usize>,
+    moves: &[Placement],
+    first: usize,
+    branches: &[usize],
+    markup: Option<&MatchMarkup>,
+) {
+    if !moves.is_empty() {
+        sgf.push('(');
+        write_line(sgf, tree, parent, moves, first, markup);
+        sgf.push(')');
+    }
+    for &branch in branches {
+        let variation = &tree.variations[branch];
+        if !variation.moves.is_empty() {
+            sgf.push('(');
+            write_line(
+                sgf,
+                tree,
+                Some(branch),
+                &variation.moves,
+                variation.start,
+                None,
+            );
+            sgf.push(')');
+        }
+    }
+}
+
+/// The variations of a line that replace its move `number`, including the ones replacing the
+/// first move of those variations
+fn alternatives(tree: &GameTree, parent: Option<usize>, number: usize) -> Vec<usize> {
+    let mut branches = Vec::new();
+    for (index, variation) in tree.variations.iter().enumerate() {
+        if variation.parent == parent && variation.start == number {
+            branches.push(index);
+            branches.extend(alternatives(tree, Some(index), number));
+        }
+    }
+    branches
+}
+
+fn push_markup(sgf: &mut String, comment: Option<&String>, markup: &MatchMarkup) {
+    let comment = match comment {
+        Some(comment) => format!("{comment}\n\n{MATCH_COMMENT}"),
+        None => MATCH_COMMENT.to_string(),
+    };
+    sgf.push_str(&format!("C[{}]", escape_text(&comment)));
+    push_points(sgf, "TR", &markup.points);
+}
+
+fn push_setup(sgf: &mut String, setup: &[Placement]) {
+    for (id, color) in [("AB", Color::Black), ("AW", Color::White)] {
+        let points = setup
+            .iter()
+            .filter(|placement| placement.color == color)
+            .map(|placement| placement.point)
+            .collect::<Vec<_>>();
+        push_points(sgf, id, &points);
+    }
+}
+
+fn push_points(sgf: &mut String, id: &str, points: &[Point]) {
+    let points = points.iter().filter(|point| !point.is_pass());
+    let mut points = points
+        .map(|point| format!("[{}]", format_sgf_point(point)))
+        .peekable();
+    if points.peek().is_some() {
+        sgf.push_str(id);
+        sgf.extend(points);
+    }
+}
+
+/// Escapes the characters that end or escape an SGF text value
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+fn color_letter(color: Color) -> char {
+    match color {
+        Color::Black => 'B',
+        Color::White => 'W',
+    }
+}
+
+fn player_name(player: &Player) -> &str {
+    match player {
+        Player::Id(_, name) | Player::Unknown(name) => name,
+    }
+}
+
+/// Formats a date as an SGF `DT` value like `2024-01-31`
+pub fn format_date(date: &SgfDate) -> String {
+    match date {
+        SgfDate::YearMonthDay(y, m, d) => format!("{y:04}-{m:02}-{d:02}"),
+        SgfDate::YearMonth(y, m) => format!("{y:04}-{m:02}"),
+        SgfDate::Year(y) => format!("{y:04}"),
+        SgfDate::Custom(date) => date.clone(),
+    }
+}
+
+/// Formats rules as an SGF `RU` value
+pub fn format_rules(rules: &Rules) -> String {
+    match rules {
+        Rules::Chinese => "Chinese".to_string(),
+        Rules::Japanese => "Japanese".to_string(),
+        Rules::Korean => "Korean".to_string(),
+        Rules::Ing => "Ing".to_string(),
+        Rules::Custom(rules) => rules.clone(),
+    }
+}
+
+/// Formats a result the way `parse_sgf_result` reads it, like `B+R` or `W+2.5 (note)`
+pub fn format_result(result: &GameResult) -> String {
+    match result {
+        GameResult::Player(color, score, note) => {
+            let score = match score {
+                Some(Score::Resignation) => "R".to_string(),
+                Some(Score::Timeout) => "T".to_string(),
+                Some(Score::Forfeit) => "F".to_string(),
+                Some(Score::Points(points)) => points.to_string(),
+                None => String::new(),
+            };
+            let result = format!("{}+{score}", color_letter(*color));
+            if note.is_empty() {
+                result
+            } else {
+                format!("{result} ({note})")
+            }
+        }
+        GameResult::Draw => "0".to_string(),
+        GameResult::Void => "Void".to_string(),
+        GameResult::Unknown(result) => result.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baduk::{Rank, Variation};
+    use crate::test_utils::{placement, test_game};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_write_metadata_and_moves() {
+        let game = Game {
+            event: "Honinbo [final]".to_string(),
+            date: Some(SgfDate::YearMonthDay(1846, 9, 11)),
+            player_black: Player::Id(1, "Shusaku".to_string()),
+            player_white: Player::Unknown("Gennan".to_string()),
+            rank_black: Rank::Dan(4),
+            rank_white: Rank::Dan(8),
+            komi: Some(0.0),
+            rules: Some(Rules::Japanese),
+            result: GameResult::Player(Color::Black, Some(Score::Points(2.0)), String::new()),
+            ..test_game(vec![
+                placement(Color::Black, 16, 3),
+                placement(Color::White, 3, 15),
+                Placement {
+                    color: Color::Black,
+                    point: Point::PASS,
+                },
+            ])
+        };
+        assert_eq!(
+            write_sgf(&game, None),
+            "(;GM[1]FF[4]CA[UTF-8]SZ[19]EV[Honinbo [final\\]]DT[1846-09-11]PB[Shusaku]BR[4d]\
+             PW[Gennan]WR[8d]KM[0]RU[Japanese]RE[B+2];B[qd];W[dp];B[])\n"
+        );
+    }
+
+    #[test]
+    fn test_write_setup_and_markup() {
+        let game = Game {
+            handicap: 2,
+            first_color: Color::White,
+            setup: vec![
+                placement(Color::Black, 15, 3),
+                placement(Color::Black, 3, 15),
+            ],
+            ..test_game(vec![
+                placement(Color::White, 16, 16),
+                placement(Color::Black, 2, 3),
+            ])
+        };
+        let markup = MatchMarkup {
+            points: vec![Point { x: 15, y: 3 }, Point { x: 16, y: 16 }],
+            last_move_matched: Some(0),
+        };
+        assert_eq!(
+            write_sgf(&game, Some(&markup)),
+            "(;GM[1]FF[4]CA[UTF-8]SZ[19]HA[2]PL[W]AB[pd][dp];W[qq]C[The search position is \
+             matched here]TR[pd][qq];B[cd])\n"
+        );
+
+        // a match in the setup stones marks the root node
+        let markup = MatchMarkup {
+            points: vec![Point { x: 15, y: 3 }],
+            last_move_matched: None,
+        };
+        assert!(write_sgf(&game, Some(&markup)).starts_with(
+            "(;GM[1]FF[4]CA[UTF-8]SZ[19]HA[2]PL[W]AB[pd][dp]C[The search position is matched \
+             here]TR[pd];W[qq]"
+        ));
+    }
+
+    #[test]
+    fn test_write_variations() {
+        let game = Game {
+            tree: Some(GameTree {
+                root_comment: Some("Lesson [1]".to_string()),
+                comments: HashMap::from([(1, "Good".to_string())]),
+                // added before the third move
+                setup: HashMap::from([(2, vec![placement(Color::White, 9, 3)])]),
+                variations: vec![
+                    // replacing the second move
+                    Variation {
+                        parent: None,
+                        start: 1,
+                        moves: vec![placement(Color::White, 2, 2)],
+                        comments: HashMap::from([(0, "Also fine".to_string())]),
+                    },
+                    // replacing the first move
+                    Variation {
+                        parent: None,
+                        start: 0,
+                        moves: vec![placement(Color::Black, 9, 9), placement(Color::White, 3, 3)],
+                        comments: HashMap::new(),
+                    },
+                    // replacing the second move of the variation above
+                    Variation {
+                        parent: Some(1),
+                        start: 1,
+                        moves: vec![placement(Color::White, 15, 15)],
+                        comments: HashMap::new(),
+                    },
+                ],
+            }),
+            ..test_game(vec![
+                placement(Color::Black, 15, 3),
+                placement(Color::White, 3, 15),
+                placement(Color::Black, 16, 16),
+            ])
+        };
+        assert_eq!(
+            write_sgf(&game, None),
+            "(;GM[1]FF[4]CA[UTF-8]SZ[19]C[Lesson [1\\]](;B[pd](;W[dp]C[Good];AW[jd];B[qq])\
+             (;W[cc]C[Also fine]))(;B[jj](;W[dd])(;W[pp])))\n"
+        );
+    }
+}
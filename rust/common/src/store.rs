@@ -0,0 +1,376 @@
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::baduk::{
+    Color, CompactGame, Game, GameInfo, GameResult, PackedPlacements, Placement, Player,
+    pack_placements, unpack_placements,
+};
+
+/// The fields of a game that searches check for every game, available without decoding its
+/// metadata in stores that keep them apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameSummary {
+    pub board_size: u8,
+    pub has_setup: bool,           // the game has setup stones
+    pub has_variations: bool,      // the game kept variations
+    pub player_black: Option<i16>, // the player ID, None for unknown players
+    pub player_white: Option<i16>,
+    pub winner: Option<Color>,
+}
+
+impl GameSummary {
+    pub fn from_info(info: &GameInfo) -> GameSummary {
+        let player_id = |player: &Player| match player {
+            Player::Id(id, _) => Some(*id),
+            Player::Unknown(_) => None,
+        };
+        GameSummary {
+            board_size: info.board_size,
+            has_setup: !info.setup.is_empty(),
+            has_variations: info.tree.as_ref().is_some_and(|t| !t.variations.is_empty()),
+            player_black: player_id(&info.player_black),
+            player_white: player_id(&info.player_white),
+            winner: match info.result {
+                GameResult::Player(winner, _, _) => Some(winner),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Read access to a collection of games, addressed by their index or path
+///
+/// Matching only needs `moves`, which every store can hand out without decoding the game, so
+/// searches work the same over games held in memory, a memory-mapped archive or a database.
+pub trait GameStore {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Path of the game at `index`, which must be less than `len()`
+    fn path(&self, index: usize) -> &str;
+
+    fn index_of(&self, path: &str) -> Option<usize>;
+
+    /// Packed moves of the game at `index`, which must be less than `len()`
+    fn moves(&self, index: usize) -> PackedPlacements<'_>;
+
+    fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>>;
+
+    /// Players, result and the rest of the metadata of the game at `index`
+    fn info(&self, index: usize) -> Cow<'_, GameInfo>;
+
+    /// Board size, player IDs and winner of the game at `index`, read from `info` unless the
+    /// store keeps them apart
+    fn summary(&self, index: usize) -> GameSummary {
+        GameSummary::from_info(&self.info(index))
+    }
+
+    /// Fully decodes the game at `index`
+    fn game(&self, index: usize) -> Game {
+        self.info(index).into_owned().into_game(
+            unpack_placements(self.moves(index).packed()).0,
+            self.captures(index),
+        )
+    }
+
+    /// Paths of all games, in index order
+    fn paths(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        Box::new((0..self.len()).map(|index| self.path(index)))
+    }
+
+    fn game_by_path(&self, path: &str) -> Option<Game> {
+        self.index_of(path).map(|index| self.game(index))
+    }
+}
+
+/// Packed games as read by `read_pack`
+impl GameStore for IndexMap<String, CompactGame> {
+    fn len(&self) -> usize {
+        IndexMap::len(self)
+    }
+
+    fn path(&self, index: usize) -> &str {
+        self.get_index(index).expect("Game index out of range").0
+    }
+
+    fn index_of(&self, path: &str) -> Option<usize> {
+        self.get_index_of(path)
+    }
+
+    fn moves(&self, index: usize) -> PackedPlacements<'_> {
+        self[index].moves()
+    }
+
+    fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>> {
+        self[index].captures()
+    }
+
+    fn info(&self, index: usize) -> Cow<'_, GameInfo> {
+        Cow::Borrowed(&self[index].info)
+    }
+
+    fn game(&self, index: usize) -> Game {
+        self[index].to_game()
+    }
+}
+
+/// Fully decoded games, with their moves also kept packed for matching
+pub struct MemoryStore {
+    games: IndexMap<String, Game>,
+    infos: Vec<GameInfo>,
+    packed_moves: Vec<Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new(games: IndexMap<String, Game>) -> MemoryStore {
+        let infos = games.values().map(GameInfo::from_game).collect();
+        let packed_moves = games
+            .values()
+            .map(|game| pack_placements(&game.moves))
+            .collect();
+        MemoryStore {
+            games,
+            infos,
+            packed_moves,
+        }
+    }
+
+    pub fn games(&self) -> &IndexMap<String, Game> {
+        &self.games
+    }
+}
+
+impl GameStore for MemoryStore {
+    fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    fn path(&self, index: usize) -> &str {
+        self.games
+            .get_index(index)
+            .expect("Game index out of range")
+            .0
+    }
+
+    fn index_of(&self, path: &str) -> Option<usize> {
+        self.games.get_index_of(path)
+    }
+
+    fn moves(&self, index: usize) -> PackedPlacements<'_> {
+        PackedPlacements::new(&self.packed_moves[index])
+    }
+
+    fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>> {
+        self.games[index].captures.clone()
+    }
+
+    fn info(&self, index: usize) -> Cow<'_, GameInfo> {
+        Cow::Borrowed(&self.infos[index])
+    }
+
+    fn game(&self, index: usize) -> Game {
+        self.games[index].clone()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteStore, write_sqlite};
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use rmp_serde::{Deserializer, Serializer};
+    use rusqlite::{Connection, OptionalExtension, params};
+    use serde::{Deserialize, Serialize};
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use super::{GameStore, GameSummary};
+    use crate::baduk::{GameInfo, PackedPlacements, Placement, pack_captures, unpack_captures};
+
+    const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        info BLOB NOT NULL,
+        moves BLOB NOT NULL,
+        captures BLOB NOT NULL
+    )";
+
+    /// Writes the games of any store into a `games` table, replacing games with the same path
+    ///
+    /// # Arguments
+    /// * `conn` - The database to write to
+    /// * `games` - The games to write, their index becomes their id
+    pub fn write_sqlite(conn: &mut Connection, games: &impl GameStore) -> rusqlite::Result<()> {
+        conn.execute(SCHEMA, [])?;
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO games (id, path, info, moves, captures)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for index in 0..games.len() {
+                let mut info = Vec::new();
+                games
+                    .info(index)
+                    .serialize(&mut Serializer::new(&mut info))
+                    .expect("Failed to serialize game metadata");
+                insert.execute(params![
+                    index as i64,
+                    games.path(index),
+                    info,
+                    games.moves(index).packed(),
+                    pack_captures(&games.captures(index)),
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Games in a SQLite database written by `write_sqlite`
+    ///
+    /// Paths, moves and summaries are loaded when opening so matching doesn't hit the database,
+    /// captures and metadata are queried per game.
+    pub struct SqliteStore {
+        conn: Connection,
+        ids: Vec<i64>,
+        paths: Vec<String>,
+        path_indices: HashMap<String, usize>,
+        packed_moves: Vec<Vec<u8>>,
+        summaries: Vec<GameSummary>,
+    }
+
+    impl SqliteStore {
+        pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<SqliteStore> {
+            SqliteStore::from_connection(Connection::open(path)?)
+        }
+
+        pub fn from_connection(conn: Connection) -> rusqlite::Result<SqliteStore> {
+            let mut ids = Vec::new();
+            let mut paths: Vec<String> = Vec::new();
+            let mut packed_moves = Vec::new();
+            let mut summaries = Vec::new();
+            {
+                let mut select =
+                    conn.prepare("SELECT id, path, moves, info FROM games ORDER BY id")?;
+                let mut rows = select.query([])?;
+                while let Some(row) = rows.next()? {
+                    ids.push(row.get(0)?);
+                    paths.push(row.get(1)?);
+                    packed_moves.push(row.get(2)?);
+                    // the metadata is decoded once here, searches check the summary of every game
+                    let info: Vec<u8> = row.get(3)?;
+                    let info = GameInfo::deserialize(&mut Deserializer::new(&info[..]))
+                        .expect("Failed to deserialize game metadata");
+                    summaries.push(GameSummary::from_info(&info));
+                }
+            }
+            let path_indices = paths
+                .iter()
+                .enumerate()
+                .map(|(index, path)| (path.clone(), index))
+                .collect();
+            Ok(SqliteStore {
+                conn,
+                ids,
+                paths,
+                path_indices,
+                packed_moves,
+                summaries,
+            })
+        }
+
+        fn column(&self, index: usize, column: &str) -> Vec<u8> {
+            self.conn
+                .query_row(
+                    &format!("SELECT {column} FROM games WHERE id = ?1"),
+                    [self.ids[index]],
+                    |row| row.get(0),
+                )
+                .optional()
+                .expect("Failed to query game")
+                .expect("Game removed from the database")
+        }
+    }
+
+    impl GameStore for SqliteStore {
+        fn len(&self) -> usize {
+            self.ids.len()
+        }
+
+        fn path(&self, index: usize) -> &str {
+            &self.paths[index]
+        }
+
+        fn index_of(&self, path: &str) -> Option<usize> {
+            self.path_indices.get(path).copied()
+        }
+
+        fn moves(&self, index: usize) -> PackedPlacements<'_> {
+            PackedPlacements::new(&self.packed_moves[index])
+        }
+
+        fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>> {
+            unpack_captures(&self.column(index, "captures"))
+        }
+
+        fn info(&self, index: usize) -> Cow<'_, GameInfo> {
+            let info = self.column(index, "info");
+            Cow::Owned(
+                GameInfo::deserialize(&mut Deserializer::new(&info[..]))
+                    .expect("Failed to deserialize game metadata"),
+            )
+        }
+
+        fn summary(&self, index: usize) -> GameSummary {
+            self.summaries[index]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baduk::{PackOptions, pack_games, read_pack};
+    use crate::test_utils::test_games;
+
+    fn check_store(store: &impl GameStore, games: &IndexMap<String, Game>) {
+        assert_eq!(store.len(), games.len());
+        assert_eq!(
+            store.paths().collect::<Vec<_>>(),
+            games.keys().map(String::as_str).collect::<Vec<_>>()
+        );
+        for (index, (path, game)) in games.iter().enumerate() {
+            assert_eq!(store.index_of(path), Some(index));
+            assert_eq!(store.moves(index).to_vec(), game.moves);
+            assert_eq!(store.captures(index), game.captures);
+            assert_eq!(*store.info(index), GameInfo::from_game(game));
+            assert_eq!(
+                store.summary(index),
+                GameSummary::from_info(&GameInfo::from_game(game))
+            );
+            assert_eq!(store.game_by_path(path).as_ref(), Some(game));
+        }
+        assert_eq!(store.index_of("missing.sgf"), None);
+    }
+
+    #[test]
+    fn test_stores() {
+        let games = test_games();
+        check_store(&MemoryStore::new(games.clone()), &games);
+
+        let (_, packed) = read_pack(&pack_games(&games, &PackOptions::default())).unwrap();
+        check_store(&packed, &games);
+
+        #[cfg(feature = "sqlite")]
+        {
+            let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+            write_sqlite(&mut conn, &packed).unwrap();
+            check_store(&SqliteStore::from_connection(conn).unwrap(), &games);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Games for tests, shared with the tests of the other crates by the `test-utils` feature
+
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+use crate::baduk::{
+    BOARD_SIZE, Color, Game, GameResult, Placement, Player, Point, Rank, SgfDate, compute_captures,
+};
+
+pub fn placement(color: Color, x: u8, y: u8) -> Placement {
+    Placement {
+        color,
+        point: Point { x, y },
+    }
+}
+
+/// A game on the full board with `moves` and no metadata, setup stones or captures, tests set
+/// the fields they check on top of it with `..test_game(moves)`
+pub fn test_game(moves: Vec<Placement>) -> Game {
+    Game {
+        event: String::new(),
+        round: String::new(),
+        location: String::new(),
+        date: None,
+        player_black: Player::Unknown(String::new()),
+        player_white: Player::Unknown(String::new()),
+        rank_black: Rank::Custom(String::new()),
+        rank_white: Rank::Custom(String::new()),
+        komi: None,
+        rules: None,
+        result: GameResult::Unknown(String::new()),
+        board_size: BOARD_SIZE,
+        handicap: 0,
+        first_color: Color::Black,
+        setup: Vec::new(),
+        tree: None,
+        moves,
+        captures: HashMap::new(),
+    }
+}
+
+/// Three games with metadata and a capture, for the tests of the stores
+pub fn test_games() -> IndexMap<String, Game> {
+    (0..3u8)
+        .map(|i| {
+            let moves = vec![
+                placement(Color::White, 0, 0),
+                placement(Color::Black, 1, 0),
+                placement(Color::White, 10, i),
+                placement(Color::Black, 0, 1),
+            ];
+            let game = Game {
+                event: format!("Event {i}"),
+                date: Some(SgfDate::Year(2000 + i as u16)),
+                player_black: Player::Id(i as i16, "Black".to_string()),
+                player_white: Player::Unknown("White".to_string()),
+                rank_black: Rank::Pro(9),
+                rank_white: Rank::Dan(1),
+                komi: Some(6.5),
+                result: GameResult::Void,
+                captures: compute_captures(&[], &moves, BOARD_SIZE),
+                ..test_game(moves)
+            };
+            (format!("games/{i}.sgf"), game)
+        })
+        .collect()
+}
@@ -22,21 +22,34 @@ struct PlayersAndDateKey {
     first_50_moves: Vec<Placement>,
 }
 
+/// Games need more moves than this to be compared with the others
+const MIN_MOVES: usize = 10;
+
 pub fn find_duplicates(
     games_vec: Vec<(String, Game)>,
 ) -> (Vec<(String, Game)>, HashSet<PossiblePlayerAlias>) {
     let possible_aliases = Mutex::new(HashSet::new());
     let unique_games = Mutex::new(Vec::<(Vec<Placement>, (String, Game))>::new());
 
+    // Short games match too many others by their moves alone
+    let games_vec: Vec<_> = games_vec
+        .into_iter()
+        .filter(|(path, game)| {
+            let is_long_enough = game.moves.len() > MIN_MOVES;
+            if !is_long_enough {
+                println!("Skipping {path}: not enough moves to compare with other games");
+            }
+            is_long_enough
+        })
+        .collect();
+
     println!("Removing duplicates from {} games...", games_vec.len());
 
     let processed_count = AtomicUsize::new(0);
     let total_games = games_vec.len();
 
     games_vec.into_par_iter().for_each(|(path, game)| {
-        assert!(game.moves.len() > 10, "Not enough moves in game");
-
-        let rotations = all_rotations(&game.moves);
+        let rotations = all_rotations(&game.moves, game.board_size);
 
         let mut is_duplicate = false;
 
@@ -44,7 +57,9 @@ pub fn find_duplicates(
             let mut unique_games_guard = unique_games.lock().unwrap();
             if let Some((_existing_moves, (_existing_path, existing_game))) = unique_games_guard
                 .iter_mut()
-                .find(|(moves, _)| *moves == position)
+                .find(|(moves, (_, existing_game))| {
+                    existing_game.board_size == game.board_size && *moves == position
+                })
             {
                 is_duplicate = true;
 
@@ -258,6 +273,10 @@ fn merge_games(existing_game: &mut Game, new_game: &Game) {
     if new_game.moves.len() > existing_game.moves.len() {
         existing_game.moves = new_game.moves.clone();
     }
+    // the variations of a commented record branch off the same moves as its duplicates
+    if existing_game.tree.is_none() {
+        existing_game.tree = new_game.tree.clone();
+    }
 
     // Apply merged values
     existing_game.event = merged_event;
@@ -272,3 +291,92 @@ fn merge_games(existing_game: &mut Game, new_game: &Game) {
     existing_game.result = merged_result;
     existing_game.rules = merged_rules;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calm_go_patterns_common::baduk::{Color, Point};
+    use std::collections::HashMap;
+
+    fn game(points: &[(u8, u8)]) -> Game {
+        let moves = points
+            .iter()
+            .enumerate()
+            .map(|(i, &(x, y))| Placement {
+                color: if i % 2 == 0 {
+                    Color::Black
+                } else {
+                    Color::White
+                },
+                point: Point { x, y },
+            })
+            .collect();
+        Game {
+            event: String::new(),
+            round: String::new(),
+            location: String::new(),
+            date: None,
+            player_black: Player::Unknown(String::new()),
+            player_white: Player::Unknown(String::new()),
+            rank_black: Rank::Custom(String::new()),
+            rank_white: Rank::Custom(String::new()),
+            komi: None,
+            rules: None,
+            result: GameResult::Unknown(String::new()),
+            board_size: 19,
+            handicap: 0,
+            first_color: Color::Black,
+            setup: Vec::new(),
+            moves,
+            captures: HashMap::new(),
+            tree: None,
+        }
+    }
+
+    const OPENING: [(u8, u8); 12] = [
+        (15, 3),
+        (3, 15),
+        (15, 15),
+        (3, 3),
+        (5, 16),
+        (2, 13),
+        (9, 15),
+        (16, 5),
+        (13, 2),
+        (17, 3),
+        (16, 2),
+        (16, 8),
+    ];
+
+    fn paths(games: &[(String, Game)]) -> Vec<&str> {
+        let mut paths: Vec<_> = games.iter().map(|(path, _)| path.as_str()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_short_games_are_skipped() {
+        let games = vec![
+            ("long".to_string(), game(&OPENING)),
+            ("short".to_string(), game(&OPENING[..MIN_MOVES])),
+        ];
+        let (unique, _) = find_duplicates(games);
+        assert_eq!(paths(&unique), ["long"]);
+    }
+
+    #[test]
+    fn test_rotated_duplicates_are_merged() {
+        let rotated: Vec<_> = OPENING.iter().map(|&(x, y)| (18 - x, 18 - y)).collect();
+        let mut other = OPENING;
+        other[11] = (2, 16);
+        let games = vec![
+            ("a".to_string(), game(&OPENING)),
+            ("b".to_string(), game(&rotated)),
+            ("c".to_string(), game(&other)),
+        ];
+        let (unique, _) = find_duplicates(games);
+        let paths = paths(&unique);
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&"c"));
+    }
+}
@@ -1,83 +1,298 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use flate2::read::GzDecoder;
 use rayon::prelude::*;
 use serde_json::Value;
-use sgf_parse::{ParseOptions, go, parse_with_options};
+use sgf_parse::{ParseOptions, SgfNode, go, parse_with_options};
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::other_formats::{load_gib, load_ngf, load_ugf};
 
 use calm_go_patterns_common::baduk::{
-    BOARD_SIZE, Color, Game, GameResult, Placement, Player, Point, Rank, parse_komi, parse_rank,
-    parse_rules, parse_sgf_date, parse_sgf_result,
+    BOARD_SIZE, Color, Game, GameResult, GameTree, Placement, Player, Point, Rank, Variation,
+    parse_komi, parse_rank, parse_rules, parse_sgf_date, parse_sgf_result,
 };
 
-pub fn load_all_sgfs(sgf_folder: &PathBuf) -> Vec<(String, Game)> {
+/// Loads the games of all SGF files below `sgf_folder`, with their variations and comments when
+/// `keep_variations` is set
+pub fn load_all_sgfs(sgf_folder: &PathBuf, keep_variations: bool) -> Vec<(String, Game)> {
     let player_aliases = load_player_aliases();
     let blocklist = load_blocklist();
-    let mut paths = Vec::new();
+    let mut files = Vec::new();
 
     println!("Loading games...");
-    collect_sgf_files(sgf_folder, &mut paths, &blocklist);
+    collect_sgf_files(sgf_folder, &mut files, &blocklist);
     println!("Read directories");
 
-    for path in &paths {
-        println!("Loading {path:?} ...");
+    for file in &files {
+        println!("Loading {:?} ...", file.path);
     }
 
-    let mut games_vec = paths
-        .par_iter()
-        .filter_map(|path| match std::fs::read_to_string(path) {
-            Ok(file_data) => match load_sgf(path, &file_data) {
-                Ok((mut game, player_black, player_white)) => {
-                    // Replace player names with id
-                    game.player_black = find_player_id(&player_black, &player_aliases);
-                    game.player_white = find_player_id(&player_white, &player_aliases);
-                    let rel_path = path
-                        .strip_prefix(sgf_folder)
-                        .unwrap()
-                        .with_extension("")
-                        .to_string_lossy()
-                        .into_owned();
-                    Some((rel_path, game))
+    let transcoded_count = AtomicUsize::new(0);
+    let mut games_vec = files
+        .into_par_iter()
+        .flat_map_iter(|file| {
+            let GameFile { path, data } = file;
+            let path = &path;
+            let games = match read_game_file(path, data) {
+                Ok((file_data, transcoded)) => {
+                    if transcoded {
+                        transcoded_count.fetch_add(1, Ordering::Relaxed);
+                    }
+                    match load_file(path, &file_data, keep_variations) {
+                        Ok(games) => games,
+                        Err(e) => {
+                            println!("Skipping {path:?}: {e}");
+                            Vec::new()
+                        }
+                    }
                 }
                 Err(e) => {
                     println!("Skipping {path:?}: {e}");
-                    None
+                    Vec::new()
                 }
-            },
-            Err(e) => {
-                println!("Skipping {path:?}: {e}");
-                None
-            }
+            };
+            let rel_path = relative_path(path, sgf_folder);
+            name_games(path, &rel_path, games, &blocklist, &player_aliases)
         })
         .collect::<Vec<_>>();
+    let transcoded_count = transcoded_count.into_inner();
+    println!("Transcoded {transcoded_count} files to UTF-8");
 
     games_vec.sort_by(|a, b| a.0.cmp(&b.0));
+    // games are looked up and downloaded by their path, it has to name one game
+    let duplicates = duplicate_paths(&games_vec);
+    assert!(
+        duplicates.is_empty(),
+        "Games with the same path, rename their files: {duplicates:?}"
+    );
     games_vec
 }
 
-fn collect_sgf_files(base_dir: &PathBuf, paths: &mut Vec<PathBuf>, blocklist: &HashSet<String>) {
+/// The paths that name more than one of the games, which are sorted by their path
+fn duplicate_paths(games: &[(String, Game)]) -> Vec<&str> {
+    let mut duplicates: Vec<&str> = games
+        .windows(2)
+        .filter(|pair| pair[0].0 == pair[1].0)
+        .map(|pair| pair[0].0.as_str())
+        .collect();
+    duplicates.dedup();
+    duplicates
+}
+
+/// Pairs the games loaded from a file with their paths, leaving out the skipped and blocked ones
+/// and replacing the player names with ids
+fn name_games(
+    path: &Path,
+    rel_path: &str,
+    games: Vec<LoadedGame>,
+    blocklist: &HashSet<String>,
+    player_aliases: &HashMap<String, i16>,
+) -> Vec<(String, Game)> {
+    let game_count = games.len();
+    let mut loaded = Vec::new();
+    for (i, result) in games.into_iter().enumerate() {
+        let game_path = game_path(rel_path, i);
+        match result {
+            Ok(_) if blocklist.contains(&game_path) => {
+                println!("Skipping blocked path: {game_path}");
+            }
+            Ok((mut game, player_black, player_white)) => {
+                game.player_black = find_player_id(&player_black, player_aliases);
+                game.player_white = find_player_id(&player_white, player_aliases);
+                loaded.push((game_path, game));
+            }
+            Err(e) if game_count == 1 => println!("Skipping {path:?}: {e}"),
+            Err(e) => println!("Skipping game {} of {path:?}: {e}", i + 1),
+        }
+    }
+    if game_count > 1 {
+        println!(
+            "Loaded {} of {game_count} games from {path:?}",
+            loaded.len()
+        );
+    }
+    loaded
+}
+
+/// The path of the game at `index` in a file. The first game keeps the path of the file, the
+/// later ones of a collection are numbered by their position in it, starting at `#2`, so they
+/// stay the same as long as the file does.
+fn game_path(rel_path: &str, index: usize) -> String {
+    if index == 0 {
+        rel_path.to_string()
+    } else {
+        format!("{rel_path}#{}", index + 1)
+    }
+}
+
+/// The extensions of the game record formats that are loaded
+const GAME_EXTENSIONS: [&str; 4] = ["sgf", "gib", "ngf", "ugf"];
+
+/// The path of a file's games relative to the games folder. SGF files leave out their
+/// extension, files in other formats keep it so their downloads find them.
+fn relative_path(path: &Path, base_dir: &Path) -> String {
+    let rel_path = path.strip_prefix(base_dir).unwrap();
+    if rel_path.extension().is_some_and(|ext| ext == "sgf") {
+        rel_path.with_extension("")
+    } else {
+        rel_path.to_path_buf()
+    }
+    .to_string_lossy()
+    .into_owned()
+}
+
+fn has_game_extension(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| GAME_EXTENSIONS.iter().any(|game_ext| ext == *game_ext))
+}
+
+/// A game file below the games folder, on its own or inside an archive
+struct GameFile {
+    path: PathBuf, // archive entries are placed below the archive, as if it were a folder
+    data: Option<Vec<u8>>, // the contents of archive entries, the other files are read when loaded
+}
+
+fn collect_sgf_files(base_dir: &PathBuf, files: &mut Vec<GameFile>, blocklist: &HashSet<String>) {
     for entry in WalkDir::new(base_dir)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "sgf"))
     {
         let path = entry.path();
-        let rel_path = path
-            .strip_prefix(base_dir)
-            .unwrap()
-            .with_extension("")
-            .to_string_lossy()
-            .into_owned();
-
-        if !blocklist.contains(&rel_path) {
-            paths.push(path.to_path_buf());
+        let found = if is_archive(path) {
+            match read_archive(path) {
+                Ok(entries) => {
+                    println!("Read {} files from archive {path:?}", entries.len());
+                    entries
+                        .into_iter()
+                        .map(|(entry_path, data)| GameFile {
+                            path: path.join(entry_path),
+                            data: Some(data),
+                        })
+                        .collect()
+                }
+                Err(e) => {
+                    println!("Skipping archive {path:?}: {e}");
+                    Vec::new()
+                }
+            }
         } else {
-            println!("Skipping blocked path: {rel_path}");
+            vec![GameFile {
+                path: path.to_path_buf(),
+                data: None,
+            }]
+        };
+
+        for file in found
+            .into_iter()
+            .filter(|file| has_game_extension(&file.path))
+        {
+            let rel_path = relative_path(&file.path, base_dir);
+            if !blocklist.contains(&rel_path) {
+                files.push(file);
+            } else {
+                println!("Skipping blocked path: {rel_path}");
+            }
+        }
+    }
+}
+
+fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    [".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+/// Reads the files of a zip, tar or gzipped tar archive with their paths inside it
+fn read_archive(path: &Path) -> std::io::Result<Vec<(PathBuf, Vec<u8>)>> {
+    let name = path.to_string_lossy();
+    if name.ends_with(".tar") {
+        return read_tar(File::open(path)?);
+    } else if !name.ends_with(".zip") {
+        return read_tar(GzDecoder::new(File::open(path)?));
+    }
+
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        // entries with paths leading out of the archive are left out
+        if let Some(entry_path) = entry.enclosed_name().filter(|_| entry.is_file()) {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            entries.push((entry_path, data));
+        }
+    }
+    Ok(entries)
+}
+
+fn read_tar(reader: impl Read) -> std::io::Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        // entries with paths leading out of the archive are left out, `./` is dropped
+        if !entry.header().entry_type().is_file()
+            || entry_path
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            continue;
         }
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        entries.push((
+            entry_path
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect(),
+            data,
+        ));
     }
+    Ok(entries)
+}
+
+/// Reads a game file as UTF-8, transcoding it from the encoding named by its SGF `CA` property
+/// or, when that is missing or wrong, the one guessed from its bytes. Returns whether it was
+/// transcoded. Archive entries come with their `data`, other files are read from `path`.
+fn read_game_file(path: &Path, data: Option<Vec<u8>>) -> std::io::Result<(String, bool)> {
+    let data = match data {
+        Some(data) => data,
+        None => std::fs::read(path)?,
+    };
+    let bytes = match String::from_utf8(data) {
+        Ok(file_data) => return Ok((file_data, false)),
+        Err(e) => e.into_bytes(),
+    };
+    let encoding = declared_encoding(&bytes)
+        .filter(|encoding| {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(&bytes)
+                .is_some()
+        })
+        .unwrap_or_else(|| {
+            let mut detector = EncodingDetector::new();
+            detector.feed(&bytes, true);
+            detector.guess(None, false)
+        });
+    let (file_data, encoding, _) = encoding.decode(&bytes);
+    Ok((file_data.into_owned(), encoding != UTF_8))
+}
+
+/// The encoding named by the first `CA` property, like `CA[GB2312]`
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let start = bytes.windows(3).position(|w| w == b"CA[")? + 3;
+    let end = start + bytes[start..].iter().position(|&b| b == b']')?;
+    Encoding::for_label(&bytes[start..end])
 }
 
 fn load_player_aliases() -> HashMap<String, i16> {
@@ -136,21 +351,62 @@ fn load_blocklist() -> HashSet<String> {
     }
 }
 
+/// A game with the names of its black and white players, or why it was skipped
+pub type LoadedGame = Result<(Game, String, String), Box<dyn std::error::Error>>;
+
+/// Loads the games of a file in any of the `GAME_EXTENSIONS` formats, going by its extension
+fn load_file(
+    path: &PathBuf,
+    file_data: &str,
+    keep_variations: bool,
+) -> Result<Vec<LoadedGame>, Box<dyn std::error::Error>> {
+    let loaded = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gib") => load_gib(file_data),
+        Some("ngf") => load_ngf(file_data),
+        Some("ugf") => load_ugf(file_data),
+        _ => return load_sgf(path, file_data, keep_variations),
+    };
+    Ok(vec![loaded.and_then(check_game)])
+}
+
+/// Skips the games that are not of two players or too short to search, in any format
+fn check_game(loaded: (Game, String, String)) -> LoadedGame {
+    let (game, player_black, player_white) = &loaded;
+    if has_multiple_players(player_black) || has_multiple_players(player_white) {
+        return Err("Player name indicates multiple players".into());
+    }
+    if game.moves.is_empty() {
+        return Err("Game has no moves".into());
+    } else if game.moves.len() < 5 {
+        return Err("Game has less than 5 moves".into());
+    }
+    Ok(loaded)
+}
+
+/// Loads every game tree of an SGF file, which holds more than one in collections like whole
+/// tournaments
 fn load_sgf(
     path: &PathBuf,
     file_data: &str,
-) -> Result<(Game, String, String), Box<dyn std::error::Error>> {
+    keep_variations: bool,
+) -> Result<Vec<LoadedGame>, Box<dyn std::error::Error>> {
     let parse_options = ParseOptions {
         lenient: true,
         ..ParseOptions::default()
     };
     let gametrees = parse_with_options(file_data, &parse_options)?;
-    let game = gametrees
+    Ok(gametrees
         .into_iter()
-        .map(|gametree| gametree.into_go_node())
-        .collect::<Result<Vec<_>, _>>()?;
+        .map(|gametree| load_game(path, &gametree.into_go_node()?, keep_variations))
+        .collect())
+}
 
+fn load_game(path: &PathBuf, root: &SgfNode<go::Prop>, keep_variations: bool) -> LoadedGame {
+    let mut setup = Vec::new();
     let mut moves = Vec::new();
+    let mut tree = keep_variations.then(GameTree::default);
+    let mut handicap = 0;
+    let mut first_color = None;
     let mut event = String::new();
     let mut round = String::new();
     let mut location = String::new();
@@ -164,7 +420,7 @@ fn load_sgf(
     let mut rules = None;
 
     // Extract metadata from root node
-    for prop in &game[0].properties {
+    for prop in &root.properties {
         match prop {
             go::Prop::EV(e) => event = e.text.to_string(),
             go::Prop::RO(r) => round = r.text.to_string(),
@@ -177,33 +433,43 @@ fn load_sgf(
             go::Prop::KM(k) => komi = parse_komi(&k.to_string()),
             go::Prop::RE(r) => result = parse_sgf_result(&r.text),
             go::Prop::RU(r) => rules = Some(parse_rules(&r.text)),
+            go::Prop::HA(h) => handicap = (*h).clamp(0, u8::MAX as i64) as u8,
+            go::Prop::PL(c) => {
+                first_color = Some(match c {
+                    sgf_parse::Color::Black => Color::Black,
+                    sgf_parse::Color::White => Color::White,
+                })
+            }
             _ => {}
         }
     }
 
-    if has_multiple_players(&player_black) || has_multiple_players(&player_white) {
-        return Err("Player name indicates multiple players".into());
-    }
-
-    if let Some(go::Prop::SZ(size)) = game[0]
+    let mut board_size = BOARD_SIZE;
+    if let Some(go::Prop::SZ(size)) = root
         .properties
         .iter()
         .find(|p| matches!(p, go::Prop::SZ(_)))
     {
-        if *size != (BOARD_SIZE, BOARD_SIZE) {
-            return Err(
-                format!("Got non-{BOARD_SIZE:?}x{BOARD_SIZE:?} board size: {size:?}").into(),
-            );
+        match *size {
+            (width, height) if width == height && (2..=BOARD_SIZE).contains(&width) => {
+                board_size = width;
+            }
+            _ => {
+                return Err(format!(
+                    "Got unsupported board size {size:?}, boards must be square and at most {BOARD_SIZE:?}x{BOARD_SIZE:?}"
+                )
+                .into());
+            }
         }
     }
 
-    for node in game[0].main_variation() {
+    for node in root.main_variation() {
         for props in &node.properties {
             match props {
                 go::Prop::W(go::Move::Move(point)) => {
-                    if point.x >= BOARD_SIZE || point.y >= BOARD_SIZE {
+                    if point.x >= board_size || point.y >= board_size {
                         println!(
-                            "Skipping move greater than board size {BOARD_SIZE:?}x{BOARD_SIZE:?}, {point:?} in file: {path:?}"
+                            "Skipping move greater than board size {board_size:?}x{board_size:?}, {point:?} in file: {path:?}"
                         );
                         break;
                     }
@@ -217,9 +483,9 @@ fn load_sgf(
                     break;
                 }
                 go::Prop::B(go::Move::Move(point)) => {
-                    if point.x >= BOARD_SIZE || point.y >= BOARD_SIZE {
+                    if point.x >= board_size || point.y >= board_size {
                         println!(
-                            "Skipping move greater than board size {BOARD_SIZE:?}x{BOARD_SIZE:?}, {point:?} in file: {path:?}"
+                            "Skipping move greater than board size {board_size:?}x{board_size:?}, {point:?} in file: {path:?}"
                         );
                         break;
                     }
@@ -232,52 +498,73 @@ fn load_sgf(
                     });
                     break;
                 }
-                go::Prop::AB(points) => {
-                    for point in points {
-                        if point.x >= BOARD_SIZE || point.y >= BOARD_SIZE {
-                            println!(
-                                "Skipping handicap placement greater than board size {BOARD_SIZE:?}x{BOARD_SIZE:?}, {point:?} in file: {path:?}"
-                            );
-                            continue;
-                        }
-                        moves.push(Placement {
-                            color: Color::Black,
-                            point: Point {
-                                x: point.x,
-                                y: point.y,
-                            },
-                        });
-                    }
+                // Passes are kept so the moves after them keep their colors
+                go::Prop::W(go::Move::Pass) | go::Prop::B(go::Move::Pass) => {
+                    moves.push(Placement {
+                        color: if matches!(props, go::Prop::B(_)) {
+                            Color::Black
+                        } else {
+                            Color::White
+                        },
+                        point: Point::PASS,
+                    });
+                    break;
                 }
-                go::Prop::AW(points) => {
+                go::Prop::AB(points) | go::Prop::AW(points) => {
+                    let color = if matches!(props, go::Prop::AB(_)) {
+                        Color::Black
+                    } else {
+                        Color::White
+                    };
                     for point in points {
-                        if point.x >= BOARD_SIZE || point.y >= BOARD_SIZE {
+                        if point.x >= board_size || point.y >= board_size {
                             println!(
-                                "Skipping handicap placement greater than board size {BOARD_SIZE:?}x{BOARD_SIZE:?}, {point:?} in file: {path:?}"
+                                "Skipping handicap placement greater than board size {board_size:?}x{board_size:?}, {point:?} in file: {path:?}"
                             );
                             continue;
                         }
-                        moves.push(Placement {
-                            color: Color::White,
+                        let placement = Placement {
+                            color,
                             point: Point {
                                 x: point.x,
                                 y: point.y,
                             },
-                        });
+                        };
+                        // Stones added after the first move are not moves, so they are only
+                        // kept with the tree of the game
+                        if moves.is_empty() {
+                            setup.push(placement);
+                        } else if let Some(tree) = &mut tree {
+                            tree.setup.entry(moves.len()).or_default().push(placement);
+                        }
                     }
                 }
                 _ => {}
             }
         }
-    }
 
-    if moves.is_empty() {
-        return Err("Game has no moves".into());
-    } else if moves.len() < 5 {
-        return Err("Game has less than 5 moves".into());
+        if let Some(tree) = &mut tree {
+            match (node_comment(node), moves.len().checked_sub(1)) {
+                (Some(comment), Some(last)) => {
+                    tree.comments.insert(last, comment);
+                }
+                // the comments before the first move are usually about the whole game
+                (Some(comment), None) => {
+                    tree.root_comment.get_or_insert(comment);
+                }
+                (None, _) => {}
+            }
+            for child in node.children.iter().skip(1) {
+                collect_variation(child, None, moves.len(), board_size, &mut tree.variations);
+            }
+        }
     }
 
-    Ok((
+    let first_color = first_color
+        .or(moves.first().map(|m| m.color))
+        .unwrap_or(Color::Black);
+
+    check_game((
         Game {
             event,
             round,
@@ -290,10 +577,285 @@ fn load_sgf(
             komi,
             result,
             rules,
+            board_size,
+            handicap,
+            first_color,
+            setup,
             moves,
             captures: HashMap::new(),
+            tree,
         },
         player_black,
         player_white,
     ))
 }
+
+fn node_comment(node: &SgfNode<go::Prop>) -> Option<String> {
+    node.properties.iter().find_map(|prop| match prop {
+        go::Prop::C(comment) => Some(comment.text.to_string()),
+        _ => None,
+    })
+}
+
+/// The move of a node in a variation, the setup stones of variations are not kept
+fn node_move(node: &SgfNode<go::Prop>, board_size: u8) -> Option<Placement> {
+    node.properties.iter().find_map(|prop| {
+        let (color, move_) = match prop {
+            go::Prop::B(move_) => (Color::Black, move_),
+            go::Prop::W(move_) => (Color::White, move_),
+            _ => return None,
+        };
+        let point = match move_ {
+            go::Move::Pass => Point::PASS,
+            go::Move::Move(point) if point.x < board_size && point.y < board_size => Point {
+                x: point.x,
+                y: point.y,
+            },
+            go::Move::Move(_) => return None,
+        };
+        Some(Placement { color, point })
+    })
+}
+
+/// Adds the line of play starting at `node` as a variation, followed by the variations branching
+/// off it
+///
+/// # Arguments
+/// * `parent` - Index of the variation it branches off, `None` for the moves of the game
+/// * `start` - Number of moves from the start of the game played before `node`
+fn collect_variation(
+    node: &SgfNode<go::Prop>,
+    parent: Option<usize>,
+    start: usize,
+    board_size: u8,
+    variations: &mut Vec<Variation>,
+) {
+    let index = variations.len();
+    variations.push(Variation {
+        parent,
+        start,
+        moves: Vec::new(),
+        comments: HashMap::new(),
+    });
+    let mut current = Some(node);
+    while let Some(node) = current {
+        if let Some(placement) = node_move(node, board_size) {
+            variations[index].moves.push(placement);
+        }
+        let played = variations[index].moves.len();
+        if let (Some(comment), Some(last)) = (node_comment(node), played.checked_sub(1)) {
+            variations[index].comments.insert(last, comment);
+        }
+        for child in node.children.iter().skip(1) {
+            collect_variation(child, Some(index), start + played, board_size, variations);
+        }
+        current = node.children.first();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_duplicates::find_duplicates;
+    use calm_go_patterns_common::sgf::write_sgf;
+
+    const GAME_A: &str = "(;GM[1]SZ[19]PB[Black A]PW[White A];B[pd];W[dp];B[pp];W[dd];B[fq];W[cn];\
+         B[jp];W[qf];B[nc];W[rd];B[qc];W[qi])";
+    const GAME_B: &str = "(;GM[1]SZ[19]PB[Black B]PW[White B];B[qd];W[dc];B[pq];W[oc];B[dp];W[po];\
+         B[cn];W[qn];B[pn];W[qm];B[ql];W[rl])";
+
+    /// Loads the games of an SGF file at `rel_path` below the games folder `games`
+    fn load(rel_path: &str, file_data: &str) -> Vec<(String, Game)> {
+        let base_dir = PathBuf::from("games");
+        let path = base_dir.join(rel_path);
+        let games = load_file(&path, file_data, false).unwrap();
+        name_games(
+            &path,
+            &relative_path(&path, &base_dir),
+            games,
+            &HashSet::new(),
+            &HashMap::new(),
+        )
+    }
+
+    fn paths(games: &[(String, Game)]) -> Vec<&str> {
+        games.iter().map(|(path, _)| path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_collection_paths() {
+        let short_game = "(;GM[1]SZ[19];B[pd];W[dp])";
+        let collection = format!("{GAME_A}\n{short_game}\n{GAME_B}");
+        let games = load("event/round.sgf", &collection);
+        // the skipped second game keeps its number out of use
+        assert_eq!(paths(&games), ["event/round", "event/round#3"]);
+        assert_eq!(
+            games[1].1.player_black,
+            Player::Unknown("Black B".to_string())
+        );
+        // the same file gets the same paths when loaded again
+        assert_eq!(paths(&load("event/round.sgf", &collection)), paths(&games));
+
+        let games = load("event/round.sgf", GAME_A);
+        assert_eq!(paths(&games), ["event/round"]);
+    }
+
+    #[test]
+    fn test_collection_duplicates() {
+        let mut games = load("collection.sgf", &format!("{GAME_A}{GAME_B}"));
+        games.extend(load("single/a.sgf", GAME_A));
+        games.extend(load("single/b.sgf", GAME_B));
+        let (unique, _) = find_duplicates(games);
+        assert_eq!(unique.len(), 2);
+        let players: HashSet<_> = unique
+            .iter()
+            .map(|(_, game)| game.player_black.clone())
+            .collect();
+        assert_eq!(
+            players,
+            HashSet::from([
+                Player::Unknown("Black A".to_string()),
+                Player::Unknown("Black B".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_duplicate_paths() {
+        // the second game of a collection and a file named like it
+        let mut games = load("event/round.sgf", &format!("{GAME_A}{GAME_B}"));
+        games.extend(load("event/round#2.sgf", GAME_B));
+        games.extend(load("event/other.sgf", GAME_A));
+        games.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(duplicate_paths(&games), ["event/round#2"]);
+        assert!(duplicate_paths(&games[..2]).is_empty());
+    }
+
+    fn load_one(file_data: &str, keep_variations: bool) -> Game {
+        let path = PathBuf::from("games/game.sgf");
+        let mut games = load_file(&path, file_data, keep_variations).unwrap();
+        assert_eq!(games.len(), 1);
+        games.pop().unwrap().unwrap().0
+    }
+
+    #[test]
+    fn test_root_comment_and_added_stones() {
+        let file_data = "(;GM[1]SZ[19]C[Game review];B[pd];W[dp];B[pp];AW[dd];W[fc];B[qf])";
+        let game = load_one(file_data, true);
+        let tree = game.tree.as_ref().unwrap();
+        assert_eq!(tree.root_comment.as_deref(), Some("Game review"));
+        // the added stone is not a move
+        assert_eq!(game.moves.len(), 5);
+        assert_eq!(game.moves[3].point, Point { x: 5, y: 2 });
+        assert_eq!(
+            tree.setup,
+            HashMap::from([(
+                3,
+                vec![Placement {
+                    color: Color::White,
+                    point: Point { x: 3, y: 3 },
+                }]
+            )])
+        );
+        assert_eq!(load_one(&write_sgf(&game, None), true), game);
+
+        // without the tree only the moves are kept
+        let game = load_one(file_data, false);
+        assert_eq!(game.tree, None);
+        assert_eq!(game.moves.len(), 5);
+    }
+
+    #[test]
+    fn test_setup_round_trip() {
+        for (file_data, first_color) in [
+            (
+                "(;GM[1]SZ[19]HA[2]AB[pd][dp];W[dd];B[pp];W[fc];B[qj];W[cn])",
+                Color::White,
+            ),
+            (
+                "(;GM[1]SZ[19]AB[pd]AW[dp];B[dd];W[pp];B[fc];W[qj];B[cn])",
+                Color::Black,
+            ),
+        ] {
+            let game = load_one(file_data, false);
+            assert_eq!(game.first_color, first_color);
+            let sgf = write_sgf(&game, None);
+            assert!(sgf.contains(&format!(
+                "PL[{}]",
+                if first_color == Color::Black {
+                    "B"
+                } else {
+                    "W"
+                }
+            )));
+            assert_eq!(load_one(&sgf, false), game);
+        }
+    }
+
+    fn fixture(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(path)
+    }
+
+    #[test]
+    fn test_read_encodings() {
+        let (file_data, transcoded) =
+            read_game_file(Path::new("game.sgf"), Some("(;PB[古力])".into())).unwrap();
+        assert_eq!(file_data, "(;PB[古力])");
+        assert!(!transcoded);
+
+        for (file, player_black) in [
+            ("gb2312.sgf", "古力"),
+            ("gb2312_no_ca.sgf", "古力"),
+            ("shift_jis.sgf", "井山裕太"),
+            ("shift_jis_no_ca.sgf", "井山裕太"),
+            // claims to be UTF-8 but is GB2312
+            ("mislabelled.sgf", "古力"),
+        ] {
+            let path = fixture(&format!("encodings/{file}"));
+            let (file_data, transcoded) = read_game_file(&path, None).unwrap();
+            assert!(transcoded, "{file}");
+            let (game, _, _) = load_file(&path, &file_data, false)
+                .unwrap()
+                .pop()
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                game.player_black,
+                Player::Unknown(player_black.to_string()),
+                "{file}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_archive_entries() {
+        let base_dir = fixture("archives");
+        let load = |blocklist: &HashSet<String>| {
+            let mut files = Vec::new();
+            collect_sgf_files(&base_dir, &mut files, blocklist);
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            files
+        };
+
+        // the games of `pro.zip` and `amateur.tar.gz` are placed below the archives, leaving out
+        // the entries that are not games or lead out of the archives
+        let files = load(&HashSet::new());
+        let paths: Vec<_> = files
+            .iter()
+            .map(|file| relative_path(&file.path, &base_dir))
+            .collect();
+        assert_eq!(paths, ["amateur.tar.gz/club/round1", "pro.zip/2024/final"]);
+        for file in files {
+            let (file_data, transcoded) = read_game_file(&file.path, file.data).unwrap();
+            assert!(!transcoded);
+            let games = load_file(&file.path, &file_data, false).unwrap();
+            assert!(games[0].is_ok());
+        }
+
+        let files = load(&HashSet::from(["amateur.tar.gz/club/round1".to_string()]));
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, base_dir.join("pro.zip/2024/final.sgf"));
+    }
+}
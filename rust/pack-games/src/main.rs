@@ -6,7 +6,10 @@ use std::fs::File;
 use std::fs::canonicalize;
 use std::io::BufReader;
 
-use calm_go_patterns_common::baduk::{GoBoard, Player, pack_games};
+use calm_go_patterns_common::archive::pack_to_archive;
+use calm_go_patterns_common::baduk::{
+    PACK_VERSION, PackOptions, Player, compute_captures, pack_games, pack_games_v1,
+};
 
 mod load_sgfs;
 use load_sgfs::load_all_sgfs;
@@ -14,13 +17,17 @@ use load_sgfs::load_all_sgfs;
 mod find_duplicates;
 use find_duplicates::find_duplicates;
 
+mod other_formats;
+
 fn main() {
     let mut sgf_folder = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     sgf_folder.push("../../frontend/public/sgfs");
     sgf_folder = canonicalize(sgf_folder).unwrap();
     println!("Loading games from '{sgf_folder:?}' ...");
 
-    let games_vec = load_all_sgfs(&sgf_folder);
+    // variations and comments make the pack larger, they are only kept when asked for
+    let keep_variations = std::env::args().any(|arg| arg == "--keep-variations");
+    let games_vec = load_all_sgfs(&sgf_folder, keep_variations);
 
     // Collect all player names from all games (including duplicates)
     println!("Collecting all player names...");
@@ -56,16 +63,7 @@ fn main() {
     let mut games: IndexMap<String, _> = final_unique_games
         .into_par_iter()
         .map(|(path, mut game)| {
-            let mut captures = HashMap::new();
-            let mut gb = GoBoard::new();
-
-            for (i, move_) in game.moves.iter().enumerate() {
-                let cs = gb.make_move(move_);
-                if !cs.is_empty() {
-                    captures.insert(i, cs);
-                }
-            }
-            game.captures = captures;
+            game.captures = compute_captures(&game.setup, &game.moves, game.board_size);
             (path, game)
         })
         .collect();
@@ -80,12 +78,33 @@ fn main() {
     });
 
     println!("Writing games.pack...");
-    let buf = pack_games(&games);
-    std::fs::write("../wasm-search/src/games.pack", buf).unwrap();
+    let options = PackOptions {
+        source: "frontend/public/sgfs".to_string(),
+        // captures are recomputed when loading the pack
+        include_captures: !std::env::args().any(|arg| arg == "--omit-captures"),
+    };
+    let buf = pack_games(&games, &options);
+    std::fs::write("../wasm-search/src/games.pack", &buf).unwrap();
 
     let metadata = std::fs::metadata("../wasm-search/src/games.pack").unwrap();
     let file_size = metadata.len();
-    println!("games.pack size: {:.2} MB", file_size as f64 / 1_048_576.0);
+    println!(
+        "games.pack version {PACK_VERSION} with {} games, size: {:.2} MB",
+        games.len(),
+        file_size as f64 / 1_048_576.0
+    );
+    let v1_size = pack_games_v1(&games, &options.source).len() as u64;
+    println!(
+        "Saved {:.2} MB ({:.1}%) compared to version 1, size: {:.2} MB",
+        v1_size.saturating_sub(file_size) as f64 / 1_048_576.0,
+        100.0 * (1.0 - file_size as f64 / v1_size as f64),
+        v1_size as f64 / 1_048_576.0
+    );
+
+    // native tools memory-map the archive instead of decoding the whole pack
+    println!("Writing games.archive...");
+    let archive = pack_to_archive(&buf).expect("Failed to read the written pack");
+    std::fs::write("games.archive", archive).unwrap();
 
     println!("Collecting unknown names from unique games...");
     let mut unknown_names = std::collections::HashSet::new();
@@ -0,0 +1,536 @@
+//! Loaders for the game records of Korean and Japanese servers that don't use SGF: Tygem's GIB,
+//! WBaduk's NGF and UGF. Their metadata is mapped to the same fields the SGF properties fill.
+
+use std::collections::HashMap;
+
+use calm_go_patterns_common::baduk::{
+    BOARD_SIZE, Color, Game, GameResult, Placement, Player, Point, Rank, Score, parse_komi,
+    parse_rank, parse_rules, parse_sgf_date,
+};
+
+use crate::load_sgfs::LoadedGame;
+
+/// Loads a Tygem GIB file. Its header lines look like `\[GAMEBLACKNAME=name (9D)\]` and its
+/// moves like `STO 0 12 1 15 3`, with the color (1 for black) and the x and y of the stone.
+pub fn load_gib(file_data: &str) -> LoadedGame {
+    let mut game = empty_game(BOARD_SIZE);
+    let mut player_black = String::new();
+    let mut player_white = String::new();
+
+    for line in file_data.lines().map(str::trim) {
+        let header = line
+            .strip_prefix("\\[")
+            .and_then(|line| line.strip_suffix("\\]"))
+            .and_then(|line| line.split_once('='));
+        if let Some((key, value)) = header {
+            match key {
+                "GAMENAME" => game.event = value.trim().to_string(),
+                "GAMEBLACKNAME" => (player_black, game.rank_black) = gib_player(value),
+                "GAMEWHITENAME" => (player_white, game.rank_white) = gib_player(value),
+                // like `GBKIND:3,GTYPE:0,GRLT:0,ZIPSU:25,GONGJE:65,...`
+                "GAMEINFOMAIN" => {
+                    let fields = value
+                        .split(',')
+                        .filter_map(|field| field.split_once(':'))
+                        .collect::<HashMap<_, _>>();
+                    // komi and score are in tenths of a point
+                    game.komi = fields
+                        .get("GONGJE")
+                        .and_then(|komi| komi.parse::<f32>().ok())
+                        .map(|komi| komi / 10.0);
+                    let score = fields
+                        .get("ZIPSU")
+                        .and_then(|score| score.parse::<f32>().ok())
+                        .map(|score| Score::Points(score / 10.0));
+                    game.result = match fields.get("GRLT").copied() {
+                        Some("0") => GameResult::Player(Color::Black, score, String::new()),
+                        Some("1") => GameResult::Player(Color::White, score, String::new()),
+                        Some("3") => resigned(Color::Black),
+                        Some("4") => resigned(Color::White),
+                        Some("7") => timed_out(Color::Black),
+                        Some("8") => timed_out(Color::White),
+                        other => GameResult::Unknown(other.unwrap_or_default().to_string()),
+                    };
+                }
+                // like `S1,R1,D0,G1,W1,Z0,C2010:05:13:12:30,...`
+                "GAMETAG" => {
+                    if let Some(date) = value.split(',').find_map(|field| field.strip_prefix('C')) {
+                        let parts = date.split(':').take(3).collect::<Vec<_>>();
+                        if parts.len() == 3 {
+                            game.date = Some(parse_sgf_date(&parts.join("-")));
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["INI", _, _, handicap, ..] => game.handicap = handicap.parse().unwrap_or(0),
+            ["STO", _, _, color, x, y, ..] => {
+                let color = if *color == "1" {
+                    Color::Black
+                } else {
+                    Color::White
+                };
+                let point = board_point(x.parse().ok(), y.parse().ok(), game.board_size)
+                    .ok_or_else(|| format!("Got move off the board: {line}"))?;
+                game.moves.push(Placement { color, point });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(finish_game(game, player_black, player_white))
+}
+
+/// Loads a WBaduk NGF file, which has its metadata on fixed lines followed by moves like
+/// `PMABBQD`, with the color and the x and y as letters from `B`, `A` being a pass
+pub fn load_ngf(file_data: &str) -> LoadedGame {
+    let lines = file_data.lines().map(str::trim).collect::<Vec<_>>();
+    if lines.len() < 12 {
+        return Err("Missing NGF header lines".into());
+    }
+    let board_size = lines[1]
+        .parse::<u8>()
+        .ok()
+        .filter(|size| (2..=BOARD_SIZE).contains(size))
+        .ok_or_else(|| format!("Got unsupported board size {:?}", lines[1]))?;
+    let mut game = empty_game(board_size);
+    game.event = lines[0].to_string();
+    let (player_white, rank_white) = ngf_player(lines[2]);
+    let (player_black, rank_black) = ngf_player(lines[3]);
+    game.rank_white = rank_white;
+    game.rank_black = rank_black;
+    game.handicap = lines[5].parse().unwrap_or(0);
+    // the komi of even games is written without its half point
+    game.komi = parse_komi(lines[7]).map(|komi| {
+        if game.handicap == 0 && komi.fract() == 0.0 {
+            komi + 0.5
+        } else {
+            komi
+        }
+    });
+    // like `20100513 [19:53]`
+    if let Some(date) = lines[8]
+        .get(..8)
+        .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))
+    {
+        game.date = Some(parse_sgf_date(&format!(
+            "{}-{}-{}",
+            &date[..4],
+            &date[4..6],
+            &date[6..]
+        )));
+    }
+    game.result = ngf_result(lines[10]);
+
+    for line in &lines[12..] {
+        let line = line.as_bytes();
+        if line.len() < 7 || !line.starts_with(b"PM") {
+            continue;
+        }
+        let color = match line[4] {
+            b'B' => Color::Black,
+            b'W' => Color::White,
+            _ => continue,
+        };
+        let point = board_point(
+            line[5].checked_sub(b'B'),
+            line[6].checked_sub(b'B'),
+            board_size,
+        )
+        .unwrap_or(Point::PASS);
+        game.moves.push(Placement { color, point });
+    }
+
+    Ok(finish_game(game, player_black, player_white))
+}
+
+/// Loads a UGF file, which has `[Header]` lines like `PlayerB=name,9d` and `[Data]` lines like
+/// `PD,B1,1,0` with the point as letters from `A`, counting rows from the bottom, the color and
+/// the move number, 0 for handicap stones
+pub fn load_ugf(file_data: &str) -> LoadedGame {
+    let mut section = "";
+    let mut header = HashMap::new();
+    let mut data = Vec::new();
+    for line in file_data.lines().map(str::trim) {
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = name;
+        } else if section == "Header" {
+            if let Some((key, value)) = line.split_once('=') {
+                header.insert(key, value);
+            }
+        } else if section == "Data" {
+            data.push(line);
+        }
+    }
+    // most values are followed by more comma separated details
+    let field = |key: &str, index: usize| {
+        header
+            .get(key)
+            .and_then(|value| value.split(',').nth(index))
+            .map_or("", str::trim)
+    };
+
+    let board_size = match field("Size", 0) {
+        "" => BOARD_SIZE,
+        size => size
+            .parse::<u8>()
+            .ok()
+            .filter(|size| (2..=BOARD_SIZE).contains(size))
+            .ok_or_else(|| format!("Got unsupported board size {size:?}"))?,
+    };
+    let mut game = empty_game(board_size);
+    game.event = field("Title", 0).to_string();
+    game.location = field("Place", 0).to_string();
+    if !field("Date", 0).is_empty() {
+        game.date = Some(parse_sgf_date(&field("Date", 0).replace('/', "-")));
+    }
+    if !field("Rule", 0).is_empty() {
+        game.rules = Some(parse_rules(field("Rule", 0)));
+    }
+    game.handicap = field("Hdcp", 0).parse().unwrap_or(0);
+    game.komi = parse_komi(field("Hdcp", 1));
+    let player_black = field("PlayerB", 0).to_string();
+    let player_white = field("PlayerW", 0).to_string();
+    game.rank_black = parse_rank(field("PlayerB", 1));
+    game.rank_white = parse_rank(field("PlayerW", 1));
+    let score = match field("Winner", 1) {
+        "R" | "r" => Some(Score::Resignation),
+        "T" | "t" => Some(Score::Timeout),
+        score => score.parse().ok().map(Score::Points),
+    };
+    game.result = match field("Winner", 0) {
+        "B" => GameResult::Player(Color::Black, score, String::new()),
+        "W" => GameResult::Player(Color::White, score, String::new()),
+        _ => GameResult::Unknown(
+            header
+                .get("Winner")
+                .copied()
+                .unwrap_or_default()
+                .to_string(),
+        ),
+    };
+
+    for line in data {
+        let fields = line.split(',').collect::<Vec<_>>();
+        let [coordinates, color, number, ..] = fields.as_slice() else {
+            continue;
+        };
+        let color = match color.chars().next() {
+            Some('B') => Color::Black,
+            Some('W') => Color::White,
+            _ => continue,
+        };
+        let coordinates = coordinates.as_bytes();
+        let point = match coordinates {
+            [x, y] => board_point(
+                x.checked_sub(b'A'),
+                y.checked_sub(b'A')
+                    .and_then(|y| (board_size - 1).checked_sub(y)),
+                board_size,
+            ),
+            _ => None,
+        }
+        .unwrap_or(Point::PASS);
+        if *number == "0" {
+            game.setup.push(Placement { color, point });
+        } else {
+            game.moves.push(Placement { color, point });
+        }
+    }
+
+    Ok(finish_game(game, player_black, player_white))
+}
+
+/// A game without any metadata or moves yet
+fn empty_game(board_size: u8) -> Game {
+    Game {
+        event: String::new(),
+        round: String::new(),
+        location: String::new(),
+        date: None,
+        player_black: Player::Unknown(String::new()),
+        player_white: Player::Unknown(String::new()),
+        rank_black: Rank::Custom(String::new()),
+        rank_white: Rank::Custom(String::new()),
+        komi: None,
+        rules: None,
+        result: GameResult::Unknown(String::new()),
+        board_size,
+        handicap: 0,
+        first_color: Color::Black,
+        setup: Vec::new(),
+        moves: Vec::new(),
+        captures: HashMap::new(),
+        tree: None,
+    }
+}
+
+/// Sets the player names and, for formats that only give the handicap, places its stones
+fn finish_game(
+    mut game: Game,
+    player_black: String,
+    player_white: String,
+) -> (Game, String, String) {
+    if game.setup.is_empty() {
+        game.setup = handicap_points(game.handicap, game.board_size)
+            .into_iter()
+            .map(|point| Placement {
+                color: Color::Black,
+                point,
+            })
+            .collect();
+    }
+    if let Some(first_move) = game.moves.first() {
+        game.first_color = first_move.color;
+    }
+    game.player_black = Player::Unknown(player_black.clone());
+    game.player_white = Player::Unknown(player_white.clone());
+    (game, player_black, player_white)
+}
+
+/// The fixed placement of handicap stones on the star points
+fn handicap_points(handicap: u8, board_size: u8) -> Vec<Point> {
+    if handicap < 2 || board_size < 7 {
+        return Vec::new();
+    }
+    let handicap = handicap.min(9) as usize;
+    let low = if board_size >= 13 { 3 } else { 2 };
+    let high = board_size - 1 - low;
+    let middle = board_size / 2;
+    let corners = [(high, low), (low, high), (high, high), (low, low)];
+    let sides = [(low, middle), (high, middle), (middle, low), (middle, high)];
+
+    let mut points = corners[..handicap.min(4)].to_vec();
+    if handicap >= 6 {
+        points.extend_from_slice(&sides[..if handicap >= 8 { 4 } else { 2 }]);
+    }
+    if handicap >= 5 && handicap % 2 == 1 {
+        points.push((middle, middle));
+    }
+    points.into_iter().map(|(x, y)| Point { x, y }).collect()
+}
+
+fn board_point(x: Option<u8>, y: Option<u8>, board_size: u8) -> Option<Point> {
+    match (x, y) {
+        (Some(x), Some(y)) if x < board_size && y < board_size => Some(Point { x, y }),
+        _ => None,
+    }
+}
+
+fn resigned(winner: Color) -> GameResult {
+    GameResult::Player(winner, Some(Score::Resignation), String::new())
+}
+
+fn timed_out(winner: Color) -> GameResult {
+    GameResult::Player(winner, Some(Score::Timeout), String::new())
+}
+
+/// Splits a GIB player like `name (9D)` into the name and the rank
+fn gib_player(value: &str) -> (String, Rank) {
+    match value.rsplit_once('(') {
+        Some((name, rank)) => (
+            name.trim().to_string(),
+            parse_rank(rank.trim_end_matches(')')),
+        ),
+        None => (value.trim().to_string(), Rank::Custom(String::new())),
+    }
+}
+
+/// Splits an NGF player like `name      9D*` into the name and the rank
+fn ngf_player(line: &str) -> (String, Rank) {
+    match line.rsplit_once(char::is_whitespace) {
+        Some((name, rank)) => (
+            name.trim().to_string(),
+            parse_rank(rank.trim_end_matches('*')),
+        ),
+        None => (line.to_string(), Rank::Custom(String::new())),
+    }
+}
+
+/// Reads NGF results like `Black wins by 5.5 points!` or `White wins by resignation!`
+fn ngf_result(line: &str) -> GameResult {
+    let lower = line.to_lowercase();
+    let winner = if lower.starts_with("black") {
+        Color::Black
+    } else if lower.starts_with("white") {
+        Color::White
+    } else {
+        return GameResult::Unknown(line.to_string());
+    };
+    let score = if lower.contains("resign") {
+        Some(Score::Resignation)
+    } else if lower.contains("time") {
+        Some(Score::Timeout)
+    } else {
+        lower
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .find_map(|number| number.parse().ok())
+            .map(Score::Points)
+    };
+    GameResult::Player(winner, score, String::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calm_go_patterns_common::baduk::{Rules, SgfDate};
+    use std::path::Path;
+
+    fn fixture(file: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/formats");
+        std::fs::read_to_string(path.join(file)).unwrap()
+    }
+
+    fn placement(color: Color, x: u8, y: u8) -> Placement {
+        Placement {
+            color,
+            point: Point { x, y },
+        }
+    }
+
+    fn pass(color: Color) -> Placement {
+        Placement {
+            color,
+            point: Point::PASS,
+        }
+    }
+
+    fn points(winner: Color, score: f32) -> GameResult {
+        GameResult::Player(winner, Some(Score::Points(score)), String::new())
+    }
+
+    #[test]
+    fn test_load_gib() {
+        let (game, player_black, player_white) = load_gib(&fixture("handicap.gib")).unwrap();
+        assert_eq!(player_black, "Lee Minjun");
+        assert_eq!(player_white, "Kim Jiwoo");
+        assert_eq!(game.rank_black, Rank::Dan(5));
+        assert_eq!(game.rank_white, Rank::Dan(7));
+        assert_eq!(game.event, "Tygem Friendly Match");
+        assert_eq!(game.date, Some(SgfDate::YearMonthDay(2010, 5, 13)));
+        assert_eq!(game.komi, Some(0.5));
+        assert_eq!(game.result, resigned(Color::White));
+        // only the number of handicap stones is given, they are placed on the star points
+        assert_eq!(game.handicap, 2);
+        assert_eq!(
+            game.setup,
+            vec![
+                placement(Color::Black, 15, 3),
+                placement(Color::Black, 3, 15)
+            ]
+        );
+        assert_eq!(game.first_color, Color::White);
+        assert_eq!(
+            game.moves,
+            vec![
+                placement(Color::White, 16, 16),
+                placement(Color::Black, 2, 15),
+                placement(Color::White, 15, 16),
+                placement(Color::Black, 3, 3),
+                placement(Color::White, 16, 2),
+                placement(Color::Black, 9, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gib_results() {
+        let file_data = fixture("handicap.gib");
+        for (grlt, result) in [
+            ("0", points(Color::Black, 3.5)),
+            ("1", points(Color::White, 3.5)),
+            ("3", resigned(Color::Black)),
+            ("4", resigned(Color::White)),
+            ("7", timed_out(Color::Black)),
+            ("8", timed_out(Color::White)),
+            ("9", GameResult::Unknown("9".to_string())),
+        ] {
+            let file_data = file_data.replace("GRLT:4,ZIPSU:0", &format!("GRLT:{grlt},ZIPSU:35"));
+            assert_eq!(
+                load_gib(&file_data).unwrap().0.result,
+                result,
+                "GRLT:{grlt}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_ngf() {
+        let file_data = fixture("even.ngf");
+        let (game, player_black, player_white) = load_ngf(&file_data).unwrap();
+        assert_eq!(player_black, "Choi Hyunwoo");
+        assert_eq!(player_white, "Park Seoyeon");
+        assert_eq!(game.rank_black, Rank::Dan(5));
+        assert_eq!(game.rank_white, Rank::Dan(6));
+        assert_eq!(game.event, "WBaduk Ladder Game");
+        assert_eq!(game.date, Some(SgfDate::YearMonthDay(2010, 5, 13)));
+        // the half point of the komi is left out for even games
+        assert_eq!(game.komi, Some(6.5));
+        assert_eq!(game.result, points(Color::White, 3.5));
+        assert_eq!(game.handicap, 0);
+        assert!(game.setup.is_empty());
+        assert_eq!(game.first_color, Color::Black);
+        assert_eq!(
+            game.moves,
+            vec![
+                placement(Color::Black, 15, 3),
+                placement(Color::White, 3, 15),
+                placement(Color::Black, 15, 15),
+                placement(Color::White, 3, 3),
+                pass(Color::Black),
+                placement(Color::White, 16, 2),
+                placement(Color::Black, 1, 14),
+            ]
+        );
+
+        // the komi of handicap games is kept as written
+        let mut lines = file_data.lines().collect::<Vec<_>>();
+        lines[5] = "3";
+        lines[7] = "0";
+        let (game, _, _) = load_ngf(&lines.join("\n")).unwrap();
+        assert_eq!(game.komi, Some(0.0));
+        assert_eq!(game.setup.len(), 3);
+    }
+
+    #[test]
+    fn test_load_ugf() {
+        let (game, player_black, player_white) = load_ugf(&fixture("handicap.ugf")).unwrap();
+        assert_eq!(player_black, "Sato Hiroshi");
+        assert_eq!(player_white, "Suzuki Kenji");
+        assert_eq!(game.rank_black, Rank::Dan(3));
+        assert_eq!(game.rank_white, Rank::Dan(5));
+        assert_eq!(game.event, "Club Championship");
+        assert_eq!(game.location, "Tokyo");
+        assert_eq!(game.date, Some(SgfDate::YearMonthDay(2010, 5, 13)));
+        assert_eq!(game.rules, Some(Rules::Japanese));
+        assert_eq!(game.komi, Some(0.5));
+        assert_eq!(game.result, points(Color::White, 2.5));
+        // the handicap stones are given as moves numbered 0, with rows counted from the bottom
+        assert_eq!(game.handicap, 2);
+        assert_eq!(
+            game.setup,
+            vec![
+                placement(Color::Black, 3, 15),
+                placement(Color::Black, 15, 3)
+            ]
+        );
+        assert_eq!(game.first_color, Color::White);
+        assert_eq!(
+            game.moves,
+            vec![
+                placement(Color::White, 16, 15),
+                placement(Color::Black, 3, 3),
+                pass(Color::White),
+                placement(Color::Black, 16, 3),
+                placement(Color::White, 2, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! A compact binary encoding of `SearchResponse` for transferring result pages
+//!
+//! The response is written as MessagePack with named fields, so that it decodes to the same
+//! shape as its JSON, except that the placements of each result, its `setup`, `moves` and their
+//! transformed versions, are left empty. They are sent separately in the `pack_placements`
+//! encoding, which takes about 1.25 bytes per move instead of around 40 bytes of JSON.
+
+use crate::SearchResponse;
+use calm_go_patterns_common::baduk::{
+    Placement, pack_placements, packed_placements_len, unpack_placements,
+};
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+
+/// # Fields
+/// * `response` - The response with the placements of its results left empty
+/// * `setup` - The packed `setup` of each result, in the order of the results
+/// * `setup_transformed` - The packed `setup_transformed` of each result
+/// * `moves` - The packed `moves` of each result
+/// * `moves_transformed` - The packed `moves_transformed` of each result
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PackedResponse {
+    pub(crate) response: SearchResponse,
+    pub(crate) setup: Vec<ByteBuf>,
+    pub(crate) setup_transformed: Vec<ByteBuf>,
+    pub(crate) moves: Vec<ByteBuf>,
+    pub(crate) moves_transformed: Vec<ByteBuf>,
+}
+
+fn take_packed(placements: &mut Vec<Placement>) -> ByteBuf {
+    ByteBuf::from(pack_placements(&std::mem::take(placements)))
+}
+
+fn unpack(packed: &ByteBuf) -> Result<Vec<Placement>, rmp_serde::decode::Error> {
+    // the count at the start of the buffer can't be trusted to fit the buffer
+    if packed_placements_len(packed) != Some(packed.len()) {
+        return Err(rmp_serde::decode::Error::Syntax(
+            "Truncated packed placements".to_string(),
+        ));
+    }
+    Ok(unpack_placements(packed).0)
+}
+
+/// Encodes a response for `decode_response`, or the `decodeSearchResponse` of the frontend
+pub fn encode_response(mut response: SearchResponse) -> Vec<u8> {
+    let mut setup = Vec::with_capacity(response.results.len());
+    let mut setup_transformed = Vec::with_capacity(response.results.len());
+    let mut moves = Vec::with_capacity(response.results.len());
+    let mut moves_transformed = Vec::with_capacity(response.results.len());
+    for result in &mut response.results {
+        setup.push(take_packed(&mut result.setup));
+        setup_transformed.push(take_packed(&mut result.setup_transformed));
+        moves.push(take_packed(&mut result.moves));
+        moves_transformed.push(take_packed(&mut result.moves_transformed));
+    }
+
+    let packed = PackedResponse {
+        response,
+        setup,
+        setup_transformed,
+        moves,
+        moves_transformed,
+    };
+    rmp_serde::to_vec_named(&packed).expect("Failed to encode SearchResponse")
+}
+
+/// Decodes a response written by `encode_response`
+pub fn decode_response(bytes: &[u8]) -> Result<SearchResponse, rmp_serde::decode::Error> {
+    let packed: PackedResponse = rmp_serde::from_slice(bytes)?;
+    let mut response = packed.response;
+    let count = response.results.len();
+    if [
+        &packed.setup,
+        &packed.setup_transformed,
+        &packed.moves,
+        &packed.moves_transformed,
+    ]
+    .iter()
+    .any(|placements| placements.len() != count)
+    {
+        return Err(rmp_serde::decode::Error::LengthMismatch(count as u32));
+    }
+    for (i, result) in response.results.iter_mut().enumerate() {
+        result.setup = unpack(&packed.setup[i])?;
+        result.setup_transformed = unpack(&packed.setup_transformed[i])?;
+        result.moves = unpack(&packed.moves[i])?;
+        result.moves_transformed = unpack(&packed.moves_transformed[i])?;
+    }
+    Ok(response)
+}
@@ -0,0 +1,198 @@
+//! Writing search results as rows for spreadsheets and data frames, as CSV or JSON Lines
+//!
+//! Each result becomes one row with its game's metadata, the transform and score of the match,
+//! and the move played next in the game, in the orientation and colors of the query.
+//!
+//! The `next_moves` of a response are left out: they are counted over all matching games, not
+//! one result, so they don't fit the rows. The JSON output of a search has them.
+
+use crate::SearchResult;
+use calm_go_patterns_common::baduk::{Color, Player};
+use calm_go_patterns_common::coords::format_gtp_point;
+use calm_go_patterns_common::sgf::{format_date, format_result, format_rules};
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// A search result flattened to plain values
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportRow {
+    pub path: String,
+    pub score: i32,
+    pub transform_score: i32,
+    pub empty_surrounding_score: i32,
+    pub empty_multiplier: i32,
+    pub move_penalty: i32,
+    pub move_matched: usize, // moves played when the position was matched, 0 for the setup
+    pub rotation: u16,       // in degrees
+    pub is_mirrored: bool,
+    pub is_inverted: bool,
+    pub variation: Option<usize>, // the variation of the game record that matched
+    pub next_move: Option<String>, // GTP vertex of the move after the match, like `Q16` or `pass`
+    pub next_color: Option<String>, // `B` or `W`, switched like the query for inverted matches
+    pub event: String,
+    pub round: String,
+    pub location: String,
+    pub date: String,
+    pub player_black: String,
+    pub player_black_id: Option<i16>,
+    pub rank_black: String,
+    pub player_white: String,
+    pub player_white_id: Option<i16>,
+    pub rank_white: String,
+    pub komi: Option<f32>,
+    pub rules: String,
+    pub result: String,
+    pub board_size: u8,
+    pub handicap: u8,
+    pub moves: usize, // length of the game, or of the variation that matched
+}
+
+/// The CSV column of each `ExportRow` field, in order
+const CSV_HEADER: [&str; 29] = [
+    "path",
+    "score",
+    "transform_score",
+    "empty_surrounding_score",
+    "empty_multiplier",
+    "move_penalty",
+    "move_matched",
+    "rotation",
+    "is_mirrored",
+    "is_inverted",
+    "variation",
+    "next_move",
+    "next_color",
+    "event",
+    "round",
+    "location",
+    "date",
+    "player_black",
+    "player_black_id",
+    "rank_black",
+    "player_white",
+    "player_white_id",
+    "rank_white",
+    "komi",
+    "rules",
+    "result",
+    "board_size",
+    "handicap",
+    "moves",
+];
+
+impl From<&SearchResult> for ExportRow {
+    fn from(result: &SearchResult) -> ExportRow {
+        let move_matched = result.last_move_matched.map_or(0, |m| m + 1);
+        let next_move = result.moves_transformed.get(move_matched);
+        let (player_black, player_black_id) = player(&result.player_black);
+        let (player_white, player_white_id) = player(&result.player_white);
+        ExportRow {
+            path: result.path.clone(),
+            score: result.score,
+            transform_score: result.score_breakdown.transform,
+            empty_surrounding_score: result.score_breakdown.empty_surrounding,
+            empty_multiplier: result.score_breakdown.empty_multiplier,
+            move_penalty: result.score_breakdown.move_penalty,
+            move_matched,
+            rotation: result.rotation as u16 * 90,
+            is_mirrored: result.is_mirrored,
+            is_inverted: result.is_inverted,
+            variation: result.variation,
+            next_move: next_move.map(|m| format_gtp_point(&m.point, result.board_size)),
+            // the stones of inverted matches were played by the other color than in the query
+            next_color: next_move.map(|m| match (m.color, result.is_inverted) {
+                (Color::Black, false) | (Color::White, true) => "B".to_string(),
+                (Color::White, false) | (Color::Black, true) => "W".to_string(),
+            }),
+            event: result.event.clone(),
+            round: result.round.clone(),
+            location: result.location.clone(),
+            date: result.date.as_ref().map(format_date).unwrap_or_default(),
+            player_black,
+            player_black_id,
+            rank_black: result.rank_black.to_string(),
+            player_white,
+            player_white_id,
+            rank_white: result.rank_white.to_string(),
+            komi: result.komi,
+            rules: result.rules.as_ref().map(format_rules).unwrap_or_default(),
+            result: format_result(&result.result),
+            board_size: result.board_size,
+            handicap: result.handicap,
+            moves: result.moves.len(),
+        }
+    }
+}
+
+impl ExportRow {
+    fn csv_values(&self) -> [String; CSV_HEADER.len()] {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        [
+            self.path.clone(),
+            self.score.to_string(),
+            self.transform_score.to_string(),
+            self.empty_surrounding_score.to_string(),
+            self.empty_multiplier.to_string(),
+            self.move_penalty.to_string(),
+            self.move_matched.to_string(),
+            self.rotation.to_string(),
+            self.is_mirrored.to_string(),
+            self.is_inverted.to_string(),
+            optional(self.variation.map(|v| v.to_string())),
+            optional(self.next_move.clone()),
+            optional(self.next_color.clone()),
+            self.event.clone(),
+            self.round.clone(),
+            self.location.clone(),
+            self.date.clone(),
+            self.player_black.clone(),
+            optional(self.player_black_id.map(|id| id.to_string())),
+            self.rank_black.clone(),
+            self.player_white.clone(),
+            optional(self.player_white_id.map(|id| id.to_string())),
+            self.rank_white.clone(),
+            optional(self.komi.map(|komi| komi.to_string())),
+            self.rules.clone(),
+            self.result.clone(),
+            self.board_size.to_string(),
+            self.handicap.to_string(),
+            self.moves.to_string(),
+        ]
+    }
+}
+
+fn player(player: &Player) -> (String, Option<i16>) {
+    match player {
+        Player::Id(id, name) => (name.clone(), Some(*id)),
+        Player::Unknown(name) => (name.clone(), None),
+    }
+}
+
+/// Quotes a CSV value when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes the results as CSV with a header line
+pub fn write_csv(results: &[SearchResult], writer: &mut impl Write) -> io::Result<()> {
+    writeln!(writer, "{}", CSV_HEADER.join(","))?;
+    for result in results {
+        let values = ExportRow::from(result).csv_values();
+        let fields = values.iter().map(|v| csv_field(v)).collect::<Vec<_>>();
+        writeln!(writer, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+/// Writes the results as JSON Lines, one `ExportRow` object per line
+pub fn write_jsonl(results: &[SearchResult], writer: &mut impl Write) -> io::Result<()> {
+    for result in results {
+        serde_json::to_writer(&mut *writer, &ExportRow::from(result))?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1646 @@
+//! Matching, ranking and filtering of games for a query position, independent of where the
+//! games are stored and of how the search is exposed
+
+pub mod binary;
+pub mod export;
+
+use calm_go_patterns_common::baduk::{
+    BOARD_SIZE, Color, Game, GameResult, GameTree, Placement, Player, Point, Rank, Rotation, Rules,
+    SgfDate, Variation, check_empty, check_within_one_quadrant, compute_captures, get_mirrored,
+    get_rotated, get_rotations, get_surrounding_points, match_game, match_game_moves,
+    match_packed_game, match_packed_game_moves, switch_colors,
+};
+use calm_go_patterns_common::sgf::{MatchMarkup, write_sgf};
+use calm_go_patterns_common::store::{GameStore, GameSummary};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A match of the query position in a game, referring to the game by its index in the store
+///
+/// Only the matches on the requested page are turned into full `SearchResult`s.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct GameMatch {
+    game_index: usize,
+    score: i32,
+    score_breakdown: ScoreBreakdown,
+    last_move_matched: Option<usize>,
+    rotation: u8,
+    is_inverted: bool,
+    is_mirrored: bool,
+    all_empty_correctly_within: u8,
+    matched_stones: Vec<MatchedStone>,
+    variation: Option<usize>, // index of the variation of the game record that matched
+}
+
+/// The query position after one of the transforms it is matched with
+struct TransformedQuery {
+    position: Vec<Placement>,
+    rotation: u8,
+    is_mirrored: bool,
+    is_inverted: bool,
+    transform_score: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct SearchResult {
+    pub path: String,
+    pub score: i32,
+    pub score_breakdown: ScoreBreakdown,
+    pub last_move_matched: Option<usize>, // None when the setup stones alone contain the position
+    pub rotation: u8,                     // 0: no rotation, 1-3: rotation index
+    pub is_inverted: bool,                // whether the colors were inverted
+    pub is_mirrored: bool,                // whether the position was mirrored
+    pub all_empty_correctly_within: u8, // distance from moves where all surrounding points are correctly empty
+    pub matched_stones: Vec<MatchedStone>, // where each query stone was played in the game
+    pub setup: Vec<Placement>,          // stones on the board before move 1
+    pub setup_transformed: Vec<Placement>, // the setup stones rotated and/or mirrored
+    pub moves: Vec<Placement>, // the actual game moves, or the line of the variation that matched
+    pub moves_transformed: Vec<Placement>, // the moves rotated and/or mirrored
+    pub variation: Option<usize>, // the variation of the game record that matched, None for the moves played
+    // Game metadata
+    pub event: String,
+    pub round: String,
+    pub location: String,
+    pub date: Option<SgfDate>,
+    pub player_black: Player,
+    pub player_white: Player,
+    pub rank_black: Rank,
+    pub rank_white: Rank,
+    pub komi: Option<f32>,
+    pub rules: Option<Rules>,
+    pub result: GameResult,
+    pub board_size: u8,
+    pub handicap: u8,
+    pub first_color: Color,
+}
+
+impl SearchResult {
+    fn new(
+        path: &str,
+        game: &Game,
+        game_match: &GameMatch,
+        setup_transformed: Vec<Placement>,
+        moves: Vec<Placement>,
+        moves_transformed: Vec<Placement>,
+    ) -> SearchResult {
+        SearchResult {
+            path: path.to_string(),
+            score: game_match.score,
+            score_breakdown: game_match.score_breakdown.clone(),
+            last_move_matched: game_match.last_move_matched,
+            rotation: game_match.rotation,
+            is_inverted: game_match.is_inverted,
+            is_mirrored: game_match.is_mirrored,
+            all_empty_correctly_within: game_match.all_empty_correctly_within,
+            matched_stones: game_match.matched_stones.clone(),
+            setup: game.setup.clone(),
+            setup_transformed,
+            moves,
+            moves_transformed,
+            variation: game_match.variation,
+            event: game.event.clone(),
+            round: game.round.clone(),
+            location: game.location.clone(),
+            date: game.date.clone(),
+            player_black: game.player_black.clone(),
+            player_white: game.player_white.clone(),
+            rank_black: game.rank_black.clone(),
+            rank_white: game.rank_white.clone(),
+            komi: game.komi,
+            rules: game.rules.clone(),
+            result: game.result.clone(),
+            board_size: game.board_size,
+            handicap: game.handicap,
+            first_color: game.first_color,
+        }
+    }
+
+    /// The game as SGF with the stones that matched the query marked, and a comment on the
+    /// move they were matched at. A match in a variation is written as the line of that variation.
+    pub fn to_sgf(&self) -> String {
+        let game = Game {
+            event: self.event.clone(),
+            round: self.round.clone(),
+            location: self.location.clone(),
+            date: self.date.clone(),
+            player_black: self.player_black.clone(),
+            player_white: self.player_white.clone(),
+            rank_black: self.rank_black.clone(),
+            rank_white: self.rank_white.clone(),
+            komi: self.komi,
+            rules: self.rules.clone(),
+            result: self.result.clone(),
+            board_size: self.board_size,
+            handicap: self.handicap,
+            first_color: self.first_color,
+            setup: self.setup.clone(),
+            moves: self.moves.clone(),
+            captures: HashMap::new(),
+            tree: None,
+        };
+        // the query stones are transformed like `setup_transformed`, which is in the same order
+        // as the setup stones of the game
+        let points = self
+            .matched_stones
+            .iter()
+            .filter_map(|stone| match stone.move_number {
+                Some(number) => self.moves.get(number).map(|m| m.point),
+                None => self
+                    .setup_transformed
+                    .iter()
+                    .position(|p| p.point == stone.placement.point)
+                    .map(|i| self.setup[i].point),
+            })
+            .collect();
+        let markup = MatchMarkup {
+            points,
+            last_move_matched: self.last_move_matched,
+        };
+        write_sgf(&game, Some(&markup))
+    }
+}
+
+/// Where a stone of the query position was played in a matched game
+///
+/// The stones are listed in the order of the query position. The transform that was applied
+/// to match them is the `rotation`, `is_mirrored` and `is_inverted` of the `SearchResult`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct MatchedStone {
+    pub placement: Placement,       // the query stone
+    pub move_number: Option<usize>, // index into the game moves where the stone was first played, None for setup stones
+    pub is_replayed: bool, // the stone was captured and its point played again before the match
+}
+
+/// The parts that make up a `SearchResult` score
+///
+/// The total score is `(transform + empty_surrounding) * empty_multiplier - move_penalty`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct ScoreBreakdown {
+    pub transform: i32, // score for the rotation, mirroring and color inversion that matched
+    pub empty_surrounding: i32, // score for rings of correctly empty points around the query stones
+    pub empty_multiplier: i32, // multiplier for all surrounding points being correctly empty
+    pub move_penalty: i32, // subtracted for the number of moves it took to match
+}
+
+impl ScoreBreakdown {
+    fn for_transform(transform: i32) -> ScoreBreakdown {
+        ScoreBreakdown {
+            transform,
+            ..Default::default()
+        }
+    }
+
+    pub fn total(&self) -> i32 {
+        (self.transform + self.empty_surrounding) * self.empty_multiplier - self.move_penalty
+    }
+}
+
+/// Weights used to rank search results, the defaults give the standard ranking
+///
+/// # Fields
+/// * `original` - Score for matching the position as is, or mirrored within one quadrant
+/// * `inverted` - Score for matching with the colors inverted
+/// * `mirrored` - Score for matching the mirrored position when it spans quadrants
+/// * `mirrored_inverted` - Score for matching mirrored with colors inverted when it spans quadrants
+/// * `rotation_penalty` - Subtracted from the transform score when the match is rotated
+/// * `empty_surrounding` - Score per ring distance for each query stone with correctly empty
+///   surroundings
+/// * `all_empty_multiplier` - Multiplier per ring where all surrounding points are correctly empty
+/// * `move_penalty` - Subtracted per move it took for the game to reach the position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct ScoreWeights {
+    pub original: i32,
+    pub inverted: i32,
+    pub mirrored: i32,
+    pub mirrored_inverted: i32,
+    pub rotation_penalty: i32,
+    pub empty_surrounding: i32,
+    pub all_empty_multiplier: i32,
+    pub move_penalty: i32,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            original: 100,
+            inverted: 90,
+            mirrored: 10,
+            mirrored_inverted: 9,
+            rotation_penalty: 1,
+            empty_surrounding: 3,
+            all_empty_multiplier: 1,
+            move_penalty: 1,
+        }
+    }
+}
+
+/// Filter for matching players by ID and optionally by color
+///
+/// # Fields
+/// * `player_id` - The ID of the player to match
+/// * `color` - Optional color constraint:
+///   - `None` - Match player regardless of color (black or white)
+///   - `Some(Color::Black)` - Only match when player is playing black
+///   - `Some(Color::White)` - Only match when player is playing white
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct PlayerFilter {
+    pub player_id: i16,
+    pub color: Option<Color>,
+}
+
+/// Filter for matching which player was on which side of the matched pattern
+///
+/// Unlike `PlayerFilter`, which only checks that a player took part in the game, these are
+/// evaluated against the colors of the query stones, taking into account whether the colors
+/// were inverted to find the match.
+///
+/// # Variants
+/// * `StonePlayedBy` - The query stone at `point` was played by the player with `player_id`
+/// * `ColorPlayedBy` - The query stones of `color` were played by the player with `player_id`
+/// * `ColorWon` - The player who played the query stones of `color` won the game
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub enum PatternFilter {
+    StonePlayedBy { point: Point, player_id: i16 },
+    ColorPlayedBy { color: Color, player_id: i16 },
+    ColorWon { color: Color },
+}
+
+impl PatternFilter {
+    pub fn matches(&self, position: &[Placement], game: &GameSummary, is_inverted: bool) -> bool {
+        let query_color = match self {
+            PatternFilter::StonePlayedBy { point, .. } => {
+                match position.iter().find(|p| p.point == *point) {
+                    Some(placement) => placement.color,
+                    None => return false,
+                }
+            }
+            PatternFilter::ColorPlayedBy { color, .. } | PatternFilter::ColorWon { color } => {
+                *color
+            }
+        };
+        // the color the query stones were actually played with in the game
+        let game_color = if is_inverted {
+            match query_color {
+                Color::Black => Color::White,
+                Color::White => Color::Black,
+            }
+        } else {
+            query_color
+        };
+        match self {
+            PatternFilter::StonePlayedBy { player_id, .. }
+            | PatternFilter::ColorPlayedBy { player_id, .. } => {
+                let player = match game_color {
+                    Color::Black => game.player_black,
+                    Color::White => game.player_white,
+                };
+                player == Some(*player_id)
+            }
+            PatternFilter::ColorWon { .. } => game.winner == Some(game_color),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct NextMove {
+    pub point: Point,
+    pub game_count: usize,
+}
+
+fn get_moves_rotation(query_rotation: &Rotation) -> Rotation {
+    // rotating the moves the opposite to the query position
+    match query_rotation {
+        Rotation::Degrees90 => Rotation::Degrees270,
+        Rotation::Degrees270 => Rotation::Degrees90,
+        _ => *query_rotation,
+    }
+}
+
+fn get_rotation_index(r: &Rotation) -> u8 {
+    match r {
+        Rotation::Degrees90 => 1,
+        Rotation::Degrees180 => 2,
+        Rotation::Degrees270 => 3,
+    }
+}
+
+pub fn get_rotation(index: u8) -> Option<Rotation> {
+    match index {
+        1 => Some(Rotation::Degrees90),
+        2 => Some(Rotation::Degrees180),
+        3 => Some(Rotation::Degrees270),
+        _ => None,
+    }
+}
+
+/// Applies the same transforms to the query position that were used to match a game
+pub fn get_matched_position(
+    position: &[Placement],
+    rotation: u8,
+    is_mirrored: bool,
+    is_inverted: bool,
+    board_size: u8,
+) -> Vec<Placement> {
+    let mut matched_position = position.to_vec();
+    if is_inverted {
+        matched_position = switch_colors(&matched_position);
+    }
+    if is_mirrored {
+        matched_position = get_mirrored(&matched_position, board_size);
+    }
+    if let Some(r) = get_rotation(rotation) {
+        matched_position = get_rotated(&matched_position, &r, board_size);
+    }
+    matched_position
+}
+
+/// Rotates and/or mirrors game moves so they line up with the query position
+pub fn get_moves_transformed(
+    moves: &[Placement],
+    rotation: u8,
+    is_mirrored: bool,
+    board_size: u8,
+) -> Vec<Placement> {
+    match (get_rotation(rotation), is_mirrored) {
+        (None, false) => moves.to_vec(),
+        (None, true) => get_mirrored(moves, board_size),
+        (Some(r), false) => get_rotated(moves, &get_moves_rotation(&r), board_size),
+        (Some(r), true) => get_rotated(&get_mirrored(moves, board_size), &r, board_size),
+    }
+}
+
+fn get_next_moves(
+    matches: &[GameMatch],
+    games: &impl GameStore,
+    variations: &HashMap<usize, GameTree>,
+    position: &[Placement],
+    next_color: Color,
+    board_size: u8,
+) -> Vec<NextMove> {
+    let mut next_moves_map: HashMap<Placement, (usize, usize)> = HashMap::new();
+    let moves_ahead = 2;
+    for game_match in matches {
+        let moves = games.moves(game_match.game_index);
+        let line = game_match
+            .variation
+            .map(|variation| variations[&game_match.game_index].line(variation, &moves.to_vec()));
+        let move_at = |i: usize| match &line {
+            Some(line) => line.get(i).copied(),
+            None => moves.get(i),
+        };
+        // the moves played up to the match, and how many of them are query stones or passes
+        let moves_to_match = game_match.last_move_matched.map_or(0, |m| m + 1);
+        let passes = (0..moves_to_match)
+            .filter_map(move_at)
+            .filter(|m| m.point.is_pass())
+            .count();
+        let stones_played = game_match
+            .matched_stones
+            .iter()
+            .filter(|stone| stone.move_number.is_some())
+            .count();
+        let mut mult: usize = if moves_to_match == stones_played + passes {
+            100
+        } else {
+            1
+        };
+        mult *= game_match.all_empty_correctly_within as usize;
+        if mult > 0 {
+            for i in 1..=moves_ahead {
+                // a pass has no point to suggest
+                if let Some(move_) = move_at(moves_to_match + i - 1).filter(|m| !m.point.is_pass())
+                {
+                    let mut move_ = get_moves_transformed(
+                        &[move_],
+                        game_match.rotation,
+                        game_match.is_mirrored,
+                        board_size,
+                    )[0];
+                    if !position.iter().any(|m| m.point == move_.point) {
+                        if game_match.is_inverted {
+                            move_.color = if move_.color == Color::White {
+                                Color::Black
+                            } else {
+                                Color::White
+                            };
+                        }
+                        if let Some((score, count)) = next_moves_map.get(&move_) {
+                            next_moves_map
+                                .insert(move_, (score + mult + moves_ahead - i, *count + 1));
+                        } else {
+                            next_moves_map.insert(move_, (mult + moves_ahead - i, 1));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let next_placements = next_moves_map.iter().collect::<Vec<_>>();
+    let mut next_moves = next_placements
+        .into_iter()
+        .filter(|(m, _)| m.color == next_color)
+        .collect::<Vec<_>>();
+
+    next_moves.sort_by(|a, b| b.1.cmp(a.1));
+    next_moves
+        .into_iter()
+        .map(|(m, (_, count))| NextMove {
+            point: m.point,
+            game_count: *count,
+        })
+        .filter(|next_move| next_move.game_count >= 50)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub enum SortBy {
+    #[default]
+    BestMatch,
+    LeastMoves,
+}
+
+/// A search for games containing a position
+///
+/// # Fields
+/// * `position` - The query stones
+/// * `next_color` - Color of the next move, used for the suggested next moves
+/// * `page` - Page number for pagination (0-based)
+/// * `page_size` - Number of results per page
+/// * `player_filters` - All of these players must have played in a matching game
+/// * `pattern_filters` - All of these must hold for which player played which side of the pattern
+/// * `sort_by` - Order of the results
+/// * `board_size` - Only games on boards of this size are searched
+///
+/// When deserializing only `position` is required, the rest default to the values of `new`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct SearchRequest {
+    pub position: Vec<Placement>,
+    #[serde(default = "default_next_color")]
+    pub next_color: Color,
+    #[serde(default)]
+    pub page: usize,
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    #[serde(default)]
+    pub player_filters: Vec<PlayerFilter>,
+    #[serde(default)]
+    pub pattern_filters: Vec<PatternFilter>,
+    #[serde(default)]
+    pub sort_by: SortBy,
+    #[serde(default = "default_board_size")]
+    pub board_size: u8,
+}
+
+fn default_next_color() -> Color {
+    Color::Black
+}
+
+fn default_board_size() -> u8 {
+    BOARD_SIZE
+}
+
+fn default_page_size() -> usize {
+    10
+}
+
+impl SearchRequest {
+    pub fn new(position: Vec<Placement>, next_color: Color) -> SearchRequest {
+        SearchRequest {
+            position,
+            next_color,
+            page: 0,
+            page_size: default_page_size(),
+            player_filters: Vec::new(),
+            pattern_filters: Vec::new(),
+            sort_by: SortBy::BestMatch,
+            board_size: BOARD_SIZE,
+        }
+    }
+
+    /// Checks that the request can be searched. The transforms of the query assume every point
+    /// is on the board.
+    pub fn validate(&self) -> Result<(), RequestError> {
+        if self.page_size == 0 {
+            return Err(RequestError::EmptyPage);
+        }
+        if !(2..=BOARD_SIZE).contains(&self.board_size) {
+            return Err(RequestError::UnsupportedBoardSize(self.board_size));
+        }
+        match self
+            .position
+            .iter()
+            .find(|p| p.point.x >= self.board_size || p.point.y >= self.board_size)
+        {
+            Some(placement) => Err(RequestError::OffBoard {
+                point: placement.point,
+                board_size: self.board_size,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Why a search request can't be searched
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestError {
+    EmptyPage,
+    UnsupportedBoardSize(u8),
+    OffBoard { point: Point, board_size: u8 },
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::EmptyPage => write!(f, "page_size must be at least 1"),
+            RequestError::UnsupportedBoardSize(size) => {
+                write!(f, "board_size must be from 2 to {BOARD_SIZE}, got {size}")
+            }
+            RequestError::OffBoard { point, board_size } => write!(
+                f,
+                "point ({}, {}) is off the {board_size}x{board_size} board",
+                point.x, point.y
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct SearchResponse {
+    pub num_results: usize,
+    pub next_moves: Vec<NextMove>,
+    pub results: Vec<SearchResult>, // the results on the requested page
+    pub total_pages: usize,
+    pub current_page: usize,
+    pub player_counts: HashMap<i16, usize>, // player_id -> count of games
+}
+
+/// Searches the games of a store, caching the matches of recent positions
+pub struct SearchEngine<S: GameStore> {
+    games: S,
+    summaries: Vec<GameSummary>, // board size and players of each game, so searches don't decode the game infos
+    setups: HashMap<usize, Vec<Placement>>, // setup stones of the games that have any
+    variations: HashMap<usize, GameTree>, // variations of the games that kept any, without comments
+    position_cache: LruCache<(u8, Vec<Placement>), Vec<GameMatch>>,
+    score_weights: ScoreWeights,
+}
+
+impl<S: GameStore> SearchEngine<S> {
+    pub fn new(games: S) -> SearchEngine<S> {
+        let summaries = (0..games.len())
+            .map(|index| games.summary(index))
+            .collect::<Vec<_>>();
+        let mut setups = HashMap::new();
+        let mut variations = HashMap::new();
+        // only the metadata of games with setup stones or variations is decoded up front
+        for (index, _) in summaries
+            .iter()
+            .enumerate()
+            .filter(|(_, summary)| summary.has_setup || summary.has_variations)
+        {
+            let info = games.info(index);
+            if !info.setup.is_empty() {
+                setups.insert(index, info.setup.clone());
+            }
+            // Only the moves of the variations are kept, their lines from the start of the game
+            // are built when a game is matched. Keeping the lines would repeat the moves of the
+            // game before each variation, many times over for the game records with the most.
+            if let Some(tree) = info.tree.as_ref().filter(|t| !t.variations.is_empty()) {
+                let variations_only = tree
+                    .variations
+                    .iter()
+                    .map(|variation| Variation {
+                        comments: HashMap::new(),
+                        ..variation.clone()
+                    })
+                    .collect();
+                variations.insert(
+                    index,
+                    GameTree {
+                        variations: variations_only,
+                        ..GameTree::default()
+                    },
+                );
+            }
+        }
+        SearchEngine {
+            games,
+            summaries,
+            setups,
+            variations,
+            position_cache: LruCache::new(std::num::NonZeroUsize::new(1000).unwrap()),
+            score_weights: ScoreWeights::default(),
+        }
+    }
+
+    pub fn games(&self) -> &S {
+        &self.games
+    }
+
+    pub fn score_weights(&self) -> &ScoreWeights {
+        &self.score_weights
+    }
+
+    /// Sets the weights used to rank search results
+    pub fn set_score_weights(&mut self, weights: ScoreWeights) {
+        if weights != self.score_weights {
+            self.score_weights = weights;
+            // cached results were ranked with the old weights
+            self.position_cache.clear();
+        }
+    }
+
+    /// Searches for games matching the position of the request
+    pub fn search(&mut self, request: &SearchRequest) -> Result<SearchResponse, RequestError> {
+        request.validate()?;
+        let position = &request.position;
+        let matches = self.filtered_matches(request);
+        let next_moves = get_next_moves(
+            &matches,
+            &self.games,
+            &self.variations,
+            position,
+            request.next_color,
+            request.board_size,
+        );
+
+        let num_results = matches.len();
+        let total_pages = num_results.div_ceil(request.page_size);
+        let current_page = request.page.min(total_pages.saturating_sub(1));
+        // Aggregate player counts from all results, excluding filtered players
+        let mut player_counts: HashMap<i16, usize> = HashMap::new();
+        let filtered_player_ids: Vec<i16> =
+            request.player_filters.iter().map(|f| f.player_id).collect();
+        for game_match in &matches {
+            let game = &self.summaries[game_match.game_index];
+            // Count both players (exclude if it's one of the filtered players)
+            for player_id in [game.player_black, game.player_white].into_iter().flatten() {
+                if !filtered_player_ids.contains(&player_id) {
+                    *player_counts.entry(player_id).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let start_idx = current_page * request.page_size;
+        let end_idx = (start_idx + request.page_size).min(num_results);
+
+        // Only the results on the requested page are materialized with their moves and metadata
+        let results = matches[start_idx..end_idx]
+            .iter()
+            .map(|game_match| {
+                let path = self.games.path(game_match.game_index);
+                // only the games on the page are fully decoded
+                let game = self.games.game(game_match.game_index);
+                let transform = |placements: &[Placement]| {
+                    get_moves_transformed(
+                        placements,
+                        game_match.rotation,
+                        game_match.is_mirrored,
+                        game.board_size,
+                    )
+                };
+                let moves = match (game_match.variation, &game.tree) {
+                    (Some(variation), Some(tree)) => tree.line(variation, &game.moves),
+                    _ => game.moves.clone(),
+                };
+                let moves_transformed = transform(&moves);
+                SearchResult::new(
+                    path,
+                    &game,
+                    game_match,
+                    transform(&game.setup),
+                    moves,
+                    moves_transformed,
+                )
+            })
+            .collect();
+
+        Ok(SearchResponse {
+            num_results,
+            next_moves: next_moves[0..next_moves.len().min(9)].to_vec(),
+            results,
+            total_pages,
+            current_page,
+            player_counts,
+        })
+    }
+
+    /// Moves played next in the games matching the request, most common first
+    ///
+    /// Only moves of `request.next_color` played in at least 50 games are suggested.
+    pub fn next_moves(&mut self, request: &SearchRequest) -> Result<Vec<NextMove>, RequestError> {
+        request.validate()?;
+        let matches = self.filtered_matches(request);
+        Ok(get_next_moves(
+            &matches,
+            &self.games,
+            &self.variations,
+            &request.position,
+            request.next_color,
+            request.board_size,
+        ))
+    }
+
+    /// Matches of the request position, sorted and filtered as requested
+    fn filtered_matches(&mut self, request: &SearchRequest) -> Vec<GameMatch> {
+        let position = &request.position;
+        let mut matches = self.match_position(position, request.board_size);
+
+        if request.sort_by == SortBy::LeastMoves {
+            matches.sort_by(|a, b| a.last_move_matched.cmp(&b.last_move_matched));
+        }
+
+        // Filter results by player filters if provided (empty array means no filter)
+        // Games must contain ALL selected players with specified colors
+        if !request.player_filters.is_empty() {
+            matches.retain(|game_match| {
+                let game = &self.summaries[game_match.game_index];
+                request.player_filters.iter().all(|filter| {
+                    let player_id = Some(filter.player_id);
+                    match filter.color {
+                        // Any color - check both black and white
+                        None => game.player_black == player_id || game.player_white == player_id,
+                        Some(Color::Black) => game.player_black == player_id,
+                        Some(Color::White) => game.player_white == player_id,
+                    }
+                })
+            });
+        }
+
+        if !request.pattern_filters.is_empty() {
+            matches.retain(|game_match| {
+                let game = &self.summaries[game_match.game_index];
+                request
+                    .pattern_filters
+                    .iter()
+                    .all(|filter| filter.matches(position, game, game_match.is_inverted))
+            });
+        }
+
+        matches
+    }
+
+    /// Gets a game as a `SearchResult` by its path, with its moves rotated and/or mirrored
+    ///
+    /// # Arguments
+    /// * `path` - The game path
+    /// * `rotation` - 0: none, 1: 90°, 2: 180°, 3: 270°
+    /// * `is_mirrored` - Whether to mirror the moves before rotation
+    pub fn result_by_path(
+        &self,
+        path: &str,
+        rotation: u8,
+        is_mirrored: bool,
+    ) -> Option<SearchResult> {
+        let game_index = self.games.index_of(path)?;
+        let game = self.games.game(game_index);
+        // the query position is transformed to match, the game is shown the other way around
+        let transform = |placements: &[Placement]| {
+            let placements = if is_mirrored {
+                get_mirrored(placements, game.board_size)
+            } else {
+                placements.to_vec()
+            };
+            match get_rotation(rotation) {
+                Some(r) => get_rotated(&placements, &r, game.board_size),
+                None => placements,
+            }
+        };
+        let game_match = GameMatch {
+            game_index,
+            rotation,
+            is_mirrored,
+            ..Default::default()
+        };
+        Some(SearchResult::new(
+            path,
+            &game,
+            &game_match,
+            transform(&game.setup),
+            game.moves.clone(),
+            transform(&game.moves),
+        ))
+    }
+
+    fn match_position(&mut self, position: &[Placement], board_size: u8) -> Vec<GameMatch> {
+        let cache_key = (board_size, position.to_vec());
+        if let Some(results) = self.position_cache.get(&cache_key) {
+            return results.clone();
+        }
+        let weights = self.score_weights.clone();
+        let game_indices = (0..self.games.len())
+            .filter(|&game_index| self.summaries[game_index].board_size == board_size)
+            .collect::<Vec<_>>();
+        if position.is_empty() {
+            let results = game_indices
+                .into_iter()
+                .map(|game_index| GameMatch {
+                    game_index,
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+            self.position_cache.put(cache_key, results.clone());
+            return results;
+        }
+        let mut results = Vec::new();
+        let (mirrored_score, mirrored_inverted_score) =
+            if check_within_one_quadrant(position, board_size) {
+                (weights.original, weights.inverted)
+            } else {
+                (weights.mirrored, weights.mirrored_inverted)
+            };
+        // every transform of the query position in the order they are tried, a game is matched
+        // by the first one that is found in it
+        let mut queries = Vec::new();
+        for (is_inverted, is_mirrored, transform_score) in [
+            (false, false, weights.original),
+            (false, true, mirrored_score),
+            (true, false, weights.inverted),
+            (true, true, mirrored_inverted_score),
+        ] {
+            let query = get_matched_position(position, 0, is_mirrored, is_inverted, board_size);
+            let rotations = get_rotations(&query, board_size);
+            queries.push(TransformedQuery {
+                position: query,
+                rotation: 0,
+                is_mirrored,
+                is_inverted,
+                transform_score,
+            });
+            for (r, rotated_position) in rotations {
+                queries.push(TransformedQuery {
+                    position: rotated_position,
+                    rotation: get_rotation_index(&r),
+                    is_mirrored,
+                    is_inverted,
+                    transform_score: transform_score - weights.rotation_penalty,
+                });
+            }
+        }
+        let find_match = |matches: &dyn Fn(&[Placement]) -> Option<Option<usize>>| {
+            queries.iter().find_map(|query| {
+                matches(&query.position).map(|last_move_matched| GameMatch {
+                    score_breakdown: ScoreBreakdown::for_transform(query.transform_score),
+                    last_move_matched,
+                    rotation: query.rotation,
+                    is_inverted: query.is_inverted,
+                    is_mirrored: query.is_mirrored,
+                    ..Default::default()
+                })
+            })
+        };
+
+        for game_index in game_indices {
+            let moves = self.games.moves(game_index);
+            let setup = self.setups.get(&game_index).map_or(&[][..], Vec::as_slice);
+            // the moves that were played are preferred over the variations of the game record
+            let matched =
+                find_match(&|query| match_packed_game(query, setup, &moves)).or_else(|| {
+                    let tree = self.variations.get(&game_index)?;
+                    let moves = moves.to_vec();
+                    (0..tree.variations.len()).find_map(|variation| {
+                        let line = tree.line(variation, &moves);
+                        find_match(&|query| match_game(query, setup, &line)).map(|game_match| {
+                            GameMatch {
+                                variation: Some(variation),
+                                ..game_match
+                            }
+                        })
+                    })
+                });
+            if let Some(game_match) = matched {
+                results.push(GameMatch {
+                    game_index,
+                    ..game_match
+                });
+            }
+        }
+        for result in &mut results {
+            let moves = self.games.moves(result.game_index);
+            let setup = self
+                .setups
+                .get(&result.game_index)
+                .map_or(&[][..], Vec::as_slice);
+            // the moves from the start of the game of a variation that matched
+            let line = result.variation.map(|variation| {
+                self.variations[&result.game_index].line(variation, &moves.to_vec())
+            });
+            let move_at = |i: usize| match &line {
+                Some(line) => line.get(i).copied(),
+                None => moves.get(i),
+            };
+
+            let matched_position = get_matched_position(
+                position,
+                result.rotation,
+                result.is_mirrored,
+                result.is_inverted,
+                board_size,
+            );
+            let move_numbers = match &line {
+                Some(line) => match_game_moves(&matched_position, setup, line),
+                None => match_packed_game_moves(&matched_position, setup, &moves),
+            }
+            .expect("Inconsistent match");
+            result.matched_stones = position
+                .iter()
+                .zip(&matched_position)
+                .zip(move_numbers)
+                .map(|((placement, matched), move_number)| {
+                    let first_after = move_number.map_or(0, |m| m + 1);
+                    MatchedStone {
+                        placement: *placement,
+                        move_number,
+                        is_replayed: result.last_move_matched.is_some_and(|last| {
+                            (first_after..=last)
+                                .filter_map(move_at)
+                                .any(|m| m.point == matched.point)
+                        }),
+                    }
+                })
+                .collect();
+
+            // the stones on the board before the last matched move, setup stones first
+            let truncated_moves = get_moves_transformed(
+                &setup
+                    .iter()
+                    .copied()
+                    .chain((0..result.last_move_matched.unwrap_or(0)).filter_map(move_at))
+                    .collect::<Vec<_>>(),
+                result.rotation,
+                result.is_mirrored,
+                board_size,
+            );
+            let mut checked = Vec::new();
+            let mut all_empty_correctly_within = 0;
+            let captures = match &line {
+                Some(line) => compute_captures(setup, line, board_size),
+                None => self.games.captures(result.game_index),
+            };
+            let captures: Vec<Point> = captures
+                .iter()
+                .filter(|(move_number, _)| Some(**move_number) <= result.last_move_matched)
+                .flat_map(|(_, cs)| cs.iter().map(|c| c.point))
+                .collect::<Vec<_>>();
+
+            for i in 1..=3 {
+                let mut all_empty = true;
+                for placement in position {
+                    let mut surrounding = get_surrounding_points(&placement.point, i, board_size);
+                    surrounding = surrounding
+                        .iter()
+                        .filter(|p| !position.iter().any(|m| m.point == **p))
+                        .filter(|p| !checked.contains(*p))
+                        .filter(|p| !captures.contains(*p))
+                        .cloned()
+                        .collect();
+                    checked.extend(surrounding.iter().cloned());
+                    if check_empty(&surrounding, &truncated_moves) {
+                        result.score_breakdown.empty_surrounding +=
+                            i as i32 * weights.empty_surrounding;
+                    } else {
+                        all_empty = false;
+                        break;
+                    }
+                }
+                if all_empty && (all_empty_correctly_within == i - 1) {
+                    all_empty_correctly_within += 1;
+                }
+            }
+            result.all_empty_correctly_within = all_empty_correctly_within;
+            // all being empty around the position we are searching is very important, hence we
+            // multiply the score
+            result.score_breakdown.empty_multiplier =
+                1 + all_empty_correctly_within as i32 * weights.all_empty_multiplier;
+            result.score_breakdown.move_penalty =
+                result.last_move_matched.map_or(0, |m| m as i32) * weights.move_penalty;
+            result.score = result.score_breakdown.total();
+        }
+
+        results.sort_by(|a, b| b.score.cmp(&a.score));
+
+        self.position_cache.put(cache_key, results.clone());
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calm_go_patterns_common::baduk::{CompactGame, GameInfo, PackedPlacements};
+    use calm_go_patterns_common::test_utils::{self, placement};
+    use indexmap::IndexMap;
+
+    fn test_game(player_black: i16, player_white: i16, moves: Vec<Placement>) -> Game {
+        Game {
+            player_black: Player::Id(player_black, "Black".to_string()),
+            player_white: Player::Id(player_white, "White".to_string()),
+            rank_black: Rank::Pro(9),
+            rank_white: Rank::Pro(9),
+            result: GameResult::Player(Color::White, None, String::new()),
+            ..test_utils::test_game(moves)
+        }
+    }
+
+    fn test_engine() -> SearchEngine<IndexMap<String, CompactGame>> {
+        let games = [
+            test_game(
+                1,
+                2,
+                vec![
+                    placement(Color::Black, 3, 3),
+                    placement(Color::White, 15, 15),
+                    placement(Color::Black, 2, 5),
+                    placement(Color::White, 16, 3),
+                    placement(Color::Black, 15, 2),
+                    placement(Color::White, 10, 10),
+                ],
+            ),
+            test_game(
+                3,
+                1,
+                vec![
+                    placement(Color::Black, 15, 3),
+                    placement(Color::White, 3, 15),
+                    placement(Color::Black, 3, 3),
+                    placement(Color::White, 2, 5),
+                ],
+            ),
+            test_game(
+                3,
+                4,
+                vec![
+                    placement(Color::Black, 9, 9),
+                    placement(Color::White, 9, 10),
+                ],
+            ),
+            Game {
+                board_size: 9,
+                ..test_game(
+                    5,
+                    6,
+                    vec![
+                        placement(Color::Black, 6, 2),
+                        placement(Color::White, 2, 6),
+                        placement(Color::Black, 3, 3),
+                    ],
+                )
+            },
+        ];
+        let games = games
+            .iter()
+            .enumerate()
+            .map(|(i, game)| (format!("games/{i}.sgf"), CompactGame::from_game(game)))
+            .collect::<IndexMap<_, _>>();
+        SearchEngine::new(games)
+    }
+
+    /// Games that keep their summaries apart, like an archive, counting the decoded metadata
+    struct CountingStore {
+        games: IndexMap<String, CompactGame>,
+        info_reads: std::cell::Cell<usize>,
+    }
+
+    impl GameStore for CountingStore {
+        fn len(&self) -> usize {
+            self.games.len()
+        }
+
+        fn path(&self, index: usize) -> &str {
+            self.games.path(index)
+        }
+
+        fn index_of(&self, path: &str) -> Option<usize> {
+            self.games.index_of(path)
+        }
+
+        fn moves(&self, index: usize) -> PackedPlacements<'_> {
+            self.games.moves(index)
+        }
+
+        fn captures(&self, index: usize) -> HashMap<usize, Vec<Placement>> {
+            self.games.captures(index)
+        }
+
+        fn info(&self, index: usize) -> std::borrow::Cow<'_, GameInfo> {
+            self.info_reads.set(self.info_reads.get() + 1);
+            self.games.info(index)
+        }
+
+        fn summary(&self, index: usize) -> GameSummary {
+            GameSummary::from_info(&self.games[index].info)
+        }
+
+        fn game(&self, index: usize) -> Game {
+            self.games.game(index)
+        }
+    }
+
+    #[test]
+    fn test_search_reads_summaries() {
+        let mut games = test_engine().games;
+        games[1].info.setup = vec![placement(Color::White, 16, 16)];
+        let mut engine = SearchEngine::new(CountingStore {
+            games,
+            info_reads: std::cell::Cell::new(0),
+        });
+        // only the game with setup stones is decoded up front
+        assert_eq!(engine.games().info_reads.get(), 1);
+
+        let mut request = SearchRequest::new(vec![placement(Color::Black, 3, 3)], Color::White);
+        request.player_filters = vec![PlayerFilter {
+            player_id: 1,
+            color: Some(Color::Black),
+        }];
+        request.pattern_filters = vec![PatternFilter::ColorWon {
+            color: Color::White,
+        }];
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        assert_eq!(response.player_counts, HashMap::from([(2, 1)]));
+        assert_eq!(engine.games().info_reads.get(), 1);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut engine = test_engine();
+        let mut request = SearchRequest::new(vec![placement(Color::Black, 3, 3)], Color::White);
+        request.page_size = 1;
+
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 2);
+        assert_eq!(response.total_pages, 2);
+        assert_eq!(response.results.len(), 1);
+        assert_eq!(response.results[0].path, "games/0.sgf");
+
+        request.page = 1;
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.current_page, 1);
+        assert_eq!(response.results[0].path, "games/1.sgf");
+
+        // the query stone was played by white in game 1
+        request.pattern_filters = vec![PatternFilter::ColorPlayedBy {
+            color: Color::Black,
+            player_id: 1,
+        }];
+        request.page = 0;
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        assert_eq!(response.results[0].path, "games/0.sgf");
+        assert_eq!(response.player_counts, HashMap::from([(1, 1), (2, 1)]));
+
+        request.pattern_filters.clear();
+        request.player_filters = vec![PlayerFilter {
+            player_id: 3,
+            color: Some(Color::Black),
+        }];
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        assert_eq!(response.results[0].path, "games/1.sgf");
+
+        let result = engine.result_by_path("games/2.sgf", 2, false).unwrap();
+        assert_eq!(result.moves_transformed[0].point, Point { x: 9, y: 9 });
+        assert_eq!(result.moves_transformed[1].point, Point { x: 9, y: 8 });
+        assert!(engine.result_by_path("missing.sgf", 0, false).is_none());
+    }
+
+    #[test]
+    fn test_matched_stones() {
+        let mut engine = test_engine();
+        let game = engine.games().game(0);
+        let position = get_rotated(&game.moves[..4], &Rotation::Degrees90, BOARD_SIZE);
+
+        let matches = engine.match_position(&position, BOARD_SIZE);
+        let game_match = matches.iter().find(|m| m.game_index == 0).unwrap();
+        let matched_position = get_matched_position(
+            &position,
+            game_match.rotation,
+            game_match.is_mirrored,
+            game_match.is_inverted,
+            BOARD_SIZE,
+        );
+
+        assert_eq!(game_match.matched_stones.len(), position.len());
+        for (i, stone) in game_match.matched_stones.iter().enumerate() {
+            assert_eq!(stone.placement, position[i]);
+            assert_eq!(game.moves[stone.move_number.unwrap()], matched_position[i]);
+            assert!(stone.move_number <= game_match.last_move_matched);
+        }
+    }
+
+    #[test]
+    fn test_moves_transformed() {
+        let mut engine = test_engine();
+        let game = engine.games().game(0);
+        let position = get_mirrored(
+            &get_rotated(&game.moves[..4], &Rotation::Degrees270, BOARD_SIZE),
+            BOARD_SIZE,
+        );
+
+        let matches = engine.match_position(&position, BOARD_SIZE);
+        let game_match = matches.iter().find(|m| m.game_index == 0).unwrap();
+        let moves_transformed = get_moves_transformed(
+            &game.moves,
+            game_match.rotation,
+            game_match.is_mirrored,
+            BOARD_SIZE,
+        );
+
+        // the transformed moves line up with the query position
+        for stone in &game_match.matched_stones {
+            assert_eq!(
+                moves_transformed[stone.move_number.unwrap()].point,
+                stone.placement.point
+            );
+        }
+    }
+
+    #[test]
+    fn test_board_sizes() {
+        let mut engine = test_engine();
+        // the 3-3 point of the 9x9 game is not searched on 19x19 boards
+        let mut request = SearchRequest::new(vec![placement(Color::Black, 3, 3)], Color::White);
+        let response = engine.search(&request).unwrap();
+        assert!(response.results.iter().all(|r| r.board_size == BOARD_SIZE));
+
+        request.board_size = 9;
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        assert_eq!(response.results[0].path, "games/3.sgf");
+        assert_eq!(response.results[0].board_size, 9);
+
+        // rotated on the 9x9 board, where 2-2 turns to 6-2
+        request.position = vec![placement(Color::Black, 2, 2), placement(Color::White, 6, 6)];
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        let result = &response.results[0];
+        assert_eq!(result.rotation, 1);
+        assert_eq!(&result.moves_transformed[..2], &request.position[..]);
+
+        request.position.clear();
+        assert_eq!(engine.search(&request).unwrap().num_results, 1);
+        request.board_size = 13;
+        assert_eq!(engine.search(&request).unwrap().num_results, 0);
+    }
+
+    #[test]
+    fn test_setup_stones() {
+        let game = Game {
+            handicap: 2,
+            first_color: Color::White,
+            setup: vec![
+                placement(Color::Black, 3, 3),
+                placement(Color::Black, 15, 15),
+            ],
+            ..test_game(
+                1,
+                2,
+                vec![
+                    placement(Color::White, 15, 3),
+                    placement(Color::Black, 3, 15),
+                    placement(Color::White, 9, 9),
+                ],
+            )
+        };
+        let games = IndexMap::from([("games/0.sgf".to_string(), CompactGame::from_game(&game))]);
+        let mut engine = SearchEngine::new(games);
+
+        // the handicap stones alone are matched before move 1
+        let mut request = SearchRequest::new(
+            vec![
+                placement(Color::Black, 3, 3),
+                placement(Color::Black, 15, 15),
+            ],
+            Color::White,
+        );
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        let result = &response.results[0];
+        assert_eq!(result.last_move_matched, None);
+        assert_eq!(result.handicap, 2);
+        assert_eq!(result.first_color, Color::White);
+        assert_eq!(result.setup_transformed.len(), 2);
+        assert!(
+            result
+                .matched_stones
+                .iter()
+                .all(|s| s.move_number.is_none())
+        );
+
+        request.position.push(placement(Color::White, 15, 3));
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        assert_eq!(response.results[0].last_move_matched, Some(0));
+    }
+
+    #[test]
+    fn test_result_to_sgf() {
+        let game = Game {
+            handicap: 2,
+            first_color: Color::White,
+            setup: vec![
+                placement(Color::Black, 3, 3),
+                placement(Color::Black, 15, 15),
+            ],
+            ..test_game(
+                1,
+                2,
+                vec![
+                    placement(Color::White, 15, 3),
+                    placement(Color::Black, 3, 15),
+                    placement(Color::White, 9, 9),
+                ],
+            )
+        };
+        let games = IndexMap::from([("games/0.sgf".to_string(), CompactGame::from_game(&game))]);
+        let mut engine = SearchEngine::new(games);
+
+        // the query is a transform of the game, the marks are on the points of the game
+        let request = SearchRequest::new(
+            vec![
+                placement(Color::Black, 15, 3),
+                placement(Color::White, 3, 3),
+            ],
+            Color::Black,
+        );
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        let sgf = response.results[0].to_sgf();
+        assert!(sgf.contains(
+            "HA[2]PL[W]AB[dd][pp];W[pd]C[The search position is matched here]TR[pp][pd];B[dp]"
+        ));
+    }
+
+    #[test]
+    fn test_passes() {
+        let pass = Placement {
+            color: Color::White,
+            point: Point::PASS,
+        };
+        let game = test_game(
+            1,
+            2,
+            vec![
+                placement(Color::Black, 3, 3),
+                pass,
+                placement(Color::Black, 15, 15),
+                placement(Color::White, 15, 3),
+            ],
+        );
+        // next moves are only suggested once enough games played them
+        let games = (0..50)
+            .map(|i| (format!("games/{i}.sgf"), CompactGame::from_game(&game)))
+            .collect::<IndexMap<_, _>>();
+        let mut engine = SearchEngine::new(games);
+
+        let request = SearchRequest::new(
+            vec![
+                placement(Color::Black, 3, 3),
+                placement(Color::Black, 15, 15),
+            ],
+            Color::White,
+        );
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 50);
+        let result = &response.results[0];
+        assert_eq!(result.last_move_matched, Some(2));
+        assert_eq!(result.moves_transformed[1], pass);
+        assert_eq!(response.next_moves.len(), 1);
+        assert_eq!(response.next_moves[0].game_count, 50);
+        assert!(!response.next_moves[0].point.is_pass());
+    }
+
+    #[test]
+    fn test_variations() {
+        let variation = Variation {
+            parent: None,
+            start: 1,
+            moves: vec![placement(Color::White, 2, 5), placement(Color::Black, 5, 2)],
+            comments: HashMap::new(),
+        };
+        let game = Game {
+            tree: Some(GameTree {
+                variations: vec![variation],
+                ..GameTree::default()
+            }),
+            ..test_game(
+                1,
+                2,
+                vec![
+                    placement(Color::Black, 3, 3),
+                    placement(Color::White, 15, 15),
+                    placement(Color::Black, 3, 15),
+                    placement(Color::White, 15, 3),
+                ],
+            )
+        };
+        let games = IndexMap::from([("games/0.sgf".to_string(), CompactGame::from_game(&game))]);
+        let mut engine = SearchEngine::new(games);
+        // only the moves of the variation are kept, not its line from the start of the game
+        assert_eq!(engine.variations[&0].variations[0].moves.len(), 2);
+
+        // only played in the variation
+        let mut request = SearchRequest::new(
+            vec![placement(Color::Black, 3, 3), placement(Color::White, 2, 5)],
+            Color::Black,
+        );
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.num_results, 1);
+        let result = &response.results[0];
+        assert_eq!(result.variation, Some(0));
+        assert_eq!(result.last_move_matched, Some(1));
+        assert_eq!(result.moves.len(), 3);
+        assert_eq!(result.moves[1], placement(Color::White, 2, 5));
+        let move_numbers = result
+            .matched_stones
+            .iter()
+            .map(|stone| stone.move_number)
+            .collect::<Vec<_>>();
+        assert_eq!(move_numbers, vec![Some(0), Some(1)]);
+
+        // the moves that were played are matched first
+        request.position.pop();
+        let response = engine.search(&request).unwrap();
+        assert_eq!(response.results[0].variation, None);
+        assert_eq!(response.results[0].moves, game.moves);
+    }
+
+    #[test]
+    fn test_score_weights() {
+        let weights: ScoreWeights =
+            serde_json::from_slice(br#"{"move_penalty": 0, "rotation_penalty": 5}"#).unwrap();
+        assert_eq!(weights.move_penalty, 0);
+        assert_eq!(weights.rotation_penalty, 5);
+        assert_eq!(weights.original, ScoreWeights::default().original);
+
+        let breakdown = ScoreBreakdown {
+            transform: 99,
+            empty_surrounding: 18,
+            empty_multiplier: 3,
+            move_penalty: 40,
+        };
+        assert_eq!(breakdown.total(), (99 + 18) * 3 - 40);
+    }
+
+    #[test]
+    fn test_pattern_filter() {
+        let position = vec![placement(Color::Black, 3, 3), placement(Color::White, 2, 5)];
+        let game = GameSummary::from_info(&GameInfo::from_game(&test_game(1, 2, position.clone())));
+
+        let stone_by_black = PatternFilter::StonePlayedBy {
+            point: Point { x: 3, y: 3 },
+            player_id: 1,
+        };
+        let black_by_white = PatternFilter::ColorPlayedBy {
+            color: Color::Black,
+            player_id: 2,
+        };
+        let white_won = PatternFilter::ColorWon {
+            color: Color::White,
+        };
+        let missing_stone = PatternFilter::StonePlayedBy {
+            point: Point { x: 10, y: 10 },
+            player_id: 1,
+        };
+
+        assert!(stone_by_black.matches(&position, &game, false));
+        assert!(!black_by_white.matches(&position, &game, false));
+        assert!(white_won.matches(&position, &game, false));
+        assert!(!missing_stone.matches(&position, &game, false));
+
+        // with inverted colors the query's black stones were played by white
+        assert!(!stone_by_black.matches(&position, &game, true));
+        assert!(black_by_white.matches(&position, &game, true));
+        assert!(!white_won.matches(&position, &game, true));
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let mut engine = test_engine();
+        let request = SearchRequest::new(vec![placement(Color::Black, 3, 3)], Color::White);
+        for (invalid, error) in [
+            (
+                SearchRequest {
+                    page_size: 0,
+                    ..request.clone()
+                },
+                RequestError::EmptyPage,
+            ),
+            (
+                SearchRequest {
+                    board_size: 1,
+                    ..request.clone()
+                },
+                RequestError::UnsupportedBoardSize(1),
+            ),
+            (
+                SearchRequest {
+                    board_size: 20,
+                    ..request.clone()
+                },
+                RequestError::UnsupportedBoardSize(20),
+            ),
+            (
+                SearchRequest {
+                    board_size: 9,
+                    position: vec![placement(Color::Black, 3, 9)],
+                    ..request.clone()
+                },
+                RequestError::OffBoard {
+                    point: Point { x: 3, y: 9 },
+                    board_size: 9,
+                },
+            ),
+        ] {
+            assert_eq!(engine.search(&invalid), Err(error.clone()));
+            assert_eq!(engine.next_moves(&invalid), Err(error));
+        }
+        assert!(engine.search(&request).is_ok());
+    }
+
+    #[test]
+    fn test_binary_response() {
+        let mut engine = test_engine();
+        let response = engine
+            .search(&SearchRequest::new(
+                vec![placement(Color::Black, 3, 3)],
+                Color::White,
+            ))
+            .unwrap();
+        assert!(!response.results.is_empty());
+
+        let encoded = binary::encode_response(response.clone());
+        assert_eq!(binary::decode_response(&encoded).unwrap(), response);
+        // the test games are only a few moves long, so the field names take most of the bytes
+        assert!(encoded.len() < serde_json::to_vec(&response).unwrap().len() * 3 / 5);
+        assert!(binary::decode_response(&encoded[..encoded.len() / 2]).is_err());
+
+        // a placements buffer that is shorter than its count of placements
+        let mut packed: binary::PackedResponse = rmp_serde::from_slice(&encoded).unwrap();
+        packed.moves[0].pop();
+        let truncated = rmp_serde::to_vec_named(&packed).unwrap();
+        assert!(binary::decode_response(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let mut engine = test_engine();
+        let mut request = SearchRequest::new(vec![placement(Color::Black, 3, 3)], Color::White);
+        request.page_size = usize::MAX;
+        let mut results = engine.search(&request).unwrap().results;
+        assert!(results.len() > 1);
+        results[0].event = "Honinbo, \"final\"".to_string();
+
+        let mut csv = Vec::new();
+        export::write_csv(&results, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), results.len() + 1);
+        assert!(lines[0].starts_with("path,score,"));
+        assert!(lines[1].contains(",\"Honinbo, \"\"final\"\"\","));
+
+        let mut jsonl = Vec::new();
+        export::write_jsonl(&results, &mut jsonl).unwrap();
+        let rows = String::from_utf8(jsonl)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), results.len());
+        assert_eq!(rows[0]["path"], results[0].path.as_str());
+        assert_eq!(rows[0]["player_black_id"], 1);
+        assert_eq!(rows[0]["move_matched"], 1);
+        assert_eq!(rows[0]["next_color"], "W");
+
+        // the first game has the query's white stones as black ones, white's next move is black's
+        let position = vec![placement(Color::White, 3, 3), placement(Color::White, 2, 5)];
+        let response = engine
+            .search(&SearchRequest::new(position, Color::Black))
+            .unwrap();
+        let result = response
+            .results
+            .iter()
+            .find(|result| result.is_inverted)
+            .unwrap();
+        assert_eq!(result.moves_transformed[3].color, Color::White);
+        let row = export::ExportRow::from(result);
+        assert_eq!(row.move_matched, 3);
+        assert_eq!(row.next_color.as_deref(), Some("B"));
+    }
+}
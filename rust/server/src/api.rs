@@ -0,0 +1,236 @@
+use calm_go_patterns_common::baduk::{PackMetadata, Player, SgfDate};
+use calm_go_patterns_common::store::GameStore;
+use calm_go_patterns_search::{SearchEngine, SearchRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Body of `POST /game`, the arguments of `SearchEngine::result_by_path`
+#[derive(Deserialize)]
+struct GameRequest {
+    path: String,
+    #[serde(default)]
+    rotation: u8,
+    #[serde(default)]
+    is_mirrored: bool,
+}
+
+/// Body of `GET /stats`, computed once when the games are loaded
+#[derive(Serialize)]
+struct Stats {
+    metadata: PackMetadata,
+    game_count: usize,
+    games_per_year: BTreeMap<u16, usize>,
+    player_game_counts: HashMap<i16, usize>, // player_id -> count of games
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// A JSON response with its HTTP status code
+#[derive(Debug)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl ApiResponse {
+    fn ok(body: &impl Serialize) -> ApiResponse {
+        ApiResponse {
+            status: 200,
+            body: serde_json::to_vec(body).expect("Failed to serialize response"),
+        }
+    }
+
+    fn error(status: u16, error: impl Into<String>) -> ApiResponse {
+        ApiResponse {
+            status,
+            body: serde_json::to_vec(&ErrorBody {
+                error: error.into(),
+            })
+            .expect("Failed to serialize error"),
+        }
+    }
+}
+
+/// The HTTP endpoints, independent of the server handling the connections
+///
+/// * `POST /search` - `SearchRequest` body, responds with a `SearchResponse`
+/// * `POST /next-moves` - `SearchRequest` body, responds with all suggested `NextMove`s
+/// * `POST /game` - `{"path", "rotation", "is_mirrored"}` body, responds with a `SearchResult`
+/// * `GET /stats` - pack metadata and game counts per year and player
+pub struct Api<S: GameStore> {
+    engine: SearchEngine<S>,
+    stats: Stats,
+}
+
+impl<S: GameStore> Api<S> {
+    pub fn new(games: S, metadata: PackMetadata) -> Api<S> {
+        let mut games_per_year = BTreeMap::new();
+        let mut player_game_counts = HashMap::new();
+        for index in 0..games.len() {
+            let info = games.info(index);
+            let year = match info.date {
+                Some(SgfDate::YearMonthDay(year, _, _))
+                | Some(SgfDate::YearMonth(year, _))
+                | Some(SgfDate::Year(year)) => Some(year),
+                Some(SgfDate::Custom(_)) | None => None,
+            };
+            if let Some(year) = year {
+                *games_per_year.entry(year).or_insert(0) += 1;
+            }
+            for player in [&info.player_black, &info.player_white] {
+                if let Player::Id(player_id, _) = player {
+                    *player_game_counts.entry(*player_id).or_insert(0) += 1;
+                }
+            }
+        }
+        let stats = Stats {
+            metadata,
+            game_count: games.len(),
+            games_per_year,
+            player_game_counts,
+        };
+        Api {
+            engine: SearchEngine::new(games),
+            stats,
+        }
+    }
+
+    pub fn handle(&mut self, method: &str, url: &str, body: &[u8]) -> ApiResponse {
+        let path = url.split('?').next().unwrap_or_default();
+        match (method, path) {
+            ("POST", "/search") => match parse_search_request(body) {
+                Ok(request) => match self.engine.search(&request) {
+                    Ok(response) => ApiResponse::ok(&response),
+                    Err(e) => ApiResponse::error(400, format!("Invalid search request: {e}")),
+                },
+                Err(response) => response,
+            },
+            ("POST", "/next-moves") => match parse_search_request(body) {
+                Ok(request) => match self.engine.next_moves(&request) {
+                    Ok(next_moves) => ApiResponse::ok(&next_moves),
+                    Err(e) => ApiResponse::error(400, format!("Invalid search request: {e}")),
+                },
+                Err(response) => response,
+            },
+            ("POST", "/game") => match serde_json::from_slice::<GameRequest>(body) {
+                Ok(request) => match self.engine.result_by_path(
+                    &request.path,
+                    request.rotation,
+                    request.is_mirrored,
+                ) {
+                    Some(result) => ApiResponse::ok(&result),
+                    None => ApiResponse::error(404, format!("No game at '{}'", request.path)),
+                },
+                Err(e) => ApiResponse::error(400, format!("Invalid game request: {e}")),
+            },
+            ("GET", "/stats") => ApiResponse::ok(&self.stats),
+            (_, "/search" | "/next-moves" | "/game" | "/stats") => {
+                ApiResponse::error(405, format!("{method} is not allowed for {path}"))
+            }
+            _ => ApiResponse::error(404, format!("No endpoint at {path}")),
+        }
+    }
+}
+
+fn parse_search_request(body: &[u8]) -> Result<SearchRequest, ApiResponse> {
+    serde_json::from_slice(body)
+        .map_err(|e| ApiResponse::error(400, format!("Invalid search request: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calm_go_patterns_common::baduk::{Color, CompactGame, Game, GameResult, Rank};
+    use calm_go_patterns_common::test_utils::{placement, test_game};
+    use indexmap::IndexMap;
+    use serde_json::Value;
+
+    fn test_api() -> Api<IndexMap<String, CompactGame>> {
+        let games = (0..3u8)
+            .map(|i| {
+                let game = Game {
+                    date: Some(SgfDate::Year(2000 + i as u16 % 2)),
+                    player_black: Player::Id(1, "Black".to_string()),
+                    player_white: Player::Id(2 + i as i16, "White".to_string()),
+                    rank_black: Rank::Pro(9),
+                    rank_white: Rank::Pro(9),
+                    result: GameResult::Void,
+                    ..test_game(vec![
+                        placement(Color::Black, 3, 3),
+                        placement(Color::White, 15, i),
+                    ])
+                };
+                (format!("games/{i}.sgf"), CompactGame::from_game(&game))
+            })
+            .collect::<IndexMap<_, _>>();
+        let metadata = PackMetadata {
+            version: 2,
+            created_at: 0,
+            game_count: games.len(),
+            source: "test".to_string(),
+        };
+        Api::new(games, metadata)
+    }
+
+    fn json(response: &ApiResponse) -> Value {
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_handle() {
+        let mut api = test_api();
+
+        let response = api.handle(
+            "POST",
+            "/search",
+            br#"{"position": [{"color": "Black", "point": {"x": 3, "y": 3}}], "page_size": 2}"#,
+        );
+        assert_eq!(response.status, 200);
+        let body = json(&response);
+        assert_eq!(body["num_results"], 3);
+        assert_eq!(body["total_pages"], 2);
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+
+        let response = api.handle(
+            "POST",
+            "/game",
+            br#"{"path": "games/1.sgf", "rotation": 0, "is_mirrored": false}"#,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(json(&response)["path"], "games/1.sgf");
+        let response = api.handle("POST", "/game", br#"{"path": "missing.sgf"}"#);
+        assert_eq!(response.status, 404);
+
+        let response = api.handle("POST", "/next-moves", br#"{"position": []}"#);
+        assert_eq!(response.status, 200);
+        assert!(json(&response).is_array());
+
+        let response = api.handle("GET", "/stats", b"");
+        assert_eq!(response.status, 200);
+        let body = json(&response);
+        assert_eq!(body["game_count"], 3);
+        assert_eq!(body["games_per_year"]["2000"], 2);
+        assert_eq!(body["player_game_counts"]["1"], 3);
+
+        assert_eq!(api.handle("POST", "/search", b"{}").status, 400);
+        assert_eq!(
+            api.handle("POST", "/search", br#"{"position": [], "page_size": 0}"#)
+                .status,
+            400
+        );
+        for body in [
+            &br#"{"position": [], "board_size": 0}"#[..],
+            br#"{"position": [], "board_size": 20}"#,
+            br#"{"position": [{"color": "Black", "point": {"x": 3, "y": 19}}]}"#,
+            br#"{"position": [{"color": "Black", "point": {"x": 9, "y": 3}}], "board_size": 9}"#,
+        ] {
+            assert_eq!(api.handle("POST", "/search", body).status, 400);
+            assert_eq!(api.handle("POST", "/next-moves", body).status, 400);
+        }
+        assert_eq!(api.handle("GET", "/search", b"").status, 405);
+        assert_eq!(api.handle("GET", "/missing", b"").status, 404);
+    }
+}
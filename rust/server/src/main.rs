@@ -0,0 +1,53 @@
+use calm_go_patterns_common::baduk::read_pack;
+use clap::Parser;
+use std::path::PathBuf;
+use tiny_http::{Header, Response, Server};
+
+mod api;
+use api::Api;
+
+/// Serve the pattern search as a JSON API on localhost
+#[derive(Parser)]
+#[command(name = "search-server")]
+struct Args {
+    /// A games.pack written by pack-games
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/../wasm-search/src/games.pack"))]
+    pack: PathBuf,
+
+    #[arg(long, default_value_t = 8787)]
+    port: u16,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    println!("Loading games from '{}' ...", args.pack.display());
+    let packed = std::fs::read(&args.pack).expect("Failed to read games pack");
+    let (metadata, games) = read_pack(&packed).expect("Failed to read games pack");
+    drop(packed);
+    let mut api = Api::new(games, metadata);
+
+    // only reachable from this machine
+    let address = ("127.0.0.1", args.port);
+    let server = Server::http(address).expect("Failed to start server");
+    println!("Listening on http://127.0.0.1:{}", args.port);
+
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("Failed to create header");
+    for mut request in server.incoming_requests() {
+        let mut body = Vec::new();
+        let response = match request.as_reader().read_to_end(&mut body) {
+            Ok(_) => api.handle(request.method().as_str(), request.url(), &body),
+            Err(e) => {
+                eprintln!("Failed to read request body: {e}");
+                continue;
+            }
+        };
+        let response = Response::from_data(response.body)
+            .with_status_code(response.status)
+            .with_header(content_type.clone());
+        if let Err(e) = request.respond(response) {
+            eprintln!("Failed to send response: {e}");
+        }
+    }
+}